@@ -1,138 +1,579 @@
-// use std::fmt;
-
-// use near_sdk::serde::{Deserialize, Serialize};
-
-// /// Enum that represents the data type of the EventLog.
-// /// The enum can either be an NftMint or an NftTransfer.
-// #[derive(Serialize, Deserialize, Debug)]
-// #[serde(tag = "event", content = "data")]
-// #[serde(rename_all = "snake_case")]
-// #[serde(crate = "near_sdk::serde")]
-// #[non_exhaustive]
-// pub enum EventLogVariant {
-//     NftMint(Vec<NftMintLog>),
-//     NftTransfer(Vec<NftTransferLog>),
-// }
-
-// /// Interface to capture data about an event
-// ///
-// /// Arguments:
-// /// * `standard`: name of standard e.g. nep171
-// /// * `version`: e.g. 1.0.0
-// /// * `event`: associate event data
-// #[derive(Serialize, Deserialize, Debug)]
-// #[serde(crate = "near_sdk::serde")]
-// pub struct EventLog {
-//     pub standard: String,
-//     pub version: String,
-
-//     // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
-//     #[serde(flatten)]
-//     pub event: EventLogVariant,
-// }
-
-// impl fmt::Display for EventLog {
-//     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-//         f.write_fmt(format_args!(
-//             "EVENT_JSON:{}",
-//             &serde_json::to_string(self).map_err(|_| fmt::Error)?
-//         ))
-//     }
-// }
-
-// /// An event log to capture token minting
-// ///
-// /// Arguments
-// /// * `owner_id`: "account.near"
-// /// * `token_ids`: ["1", "abc"]
-// /// * `memo`: optional message
-// #[derive(Serialize, Deserialize, Debug)]
-// #[serde(crate = "near_sdk::serde")]
-// pub struct NftMintLog {
-//     pub owner_id: String,
-//     pub token_ids: Vec<String>,
-
-//     #[serde(skip_serializing_if = "Option::is_none")]
-//     pub memo: Option<String>,
-// }
-
-// /// An event log to capture token transfer
-// ///
-// /// Arguments
-// /// * `authorized_id`: approved account to transfer
-// /// * `old_owner_id`: "owner.near"
-// /// * `new_owner_id`: "receiver.near"
-// /// * `token_ids`: ["1", "12345abc"]
-// /// * `memo`: optional message
-// #[derive(Serialize, Deserialize, Debug)]
-// #[serde(crate = "near_sdk::serde")]
-// pub struct NftTransferLog {
-//     #[serde(skip_serializing_if = "Option::is_none")]
-//     pub authorized_id: Option<String>,
-
-//     pub old_owner_id: String,
-//     pub new_owner_id: String,
-//     pub token_ids: Vec<String>,
-
-//     #[serde(skip_serializing_if = "Option::is_none")]
-//     pub memo: Option<String>,
-// }
-
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-
-//     #[test]
-//     fn nep_format_vector() {
-//         let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"foundation.near","token_ids":["aurora","proximitylabs"]},{"owner_id":"user1.near","token_ids":["meme"]}]}"#;
-//         let log = EventLog {
-//             standard: "nep171".to_string(),
-//             version: "1.0.0".to_string(),
-//             event: EventLogVariant::NftMint(vec![
-//                 NftMintLog {
-//                     owner_id: "foundation.near".to_owned(),
-//                     token_ids: vec!["aurora".to_string(), "proximitylabs".to_string()],
-//                     memo: None,
-//                 },
-//                 NftMintLog {
-//                     owner_id: "user1.near".to_owned(),
-//                     token_ids: vec!["meme".to_string()],
-//                     memo: None,
-//                 },
-//             ]),
-//         };
-//         assert_eq!(expected, log.to_string());
-//     }
-
-//     #[test]
-//     fn nep_format_mint() {
-//         let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"foundation.near","token_ids":["aurora","proximitylabs"]}]}"#;
-//         let log = EventLog {
-//             standard: "nep171".to_string(),
-//             version: "1.0.0".to_string(),
-//             event: EventLogVariant::NftMint(vec![NftMintLog {
-//                 owner_id: "foundation.near".to_owned(),
-//                 token_ids: vec!["aurora".to_string(), "proximitylabs".to_string()],
-//                 memo: None,
-//             }]),
-//         };
-//         assert_eq!(expected, log.to_string());
-//     }
-
-//     #[test]
-//     fn nep_format_transfer_all_fields() {
-//         let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"authorized_id":"market.near","old_owner_id":"user1.near","new_owner_id":"user2.near","token_ids":["token"],"memo":"Go Team!"}]}"#;
-//         let log = EventLog {
-//             standard: "nep171".to_string(),
-//             version: "1.0.0".to_string(),
-//             event: EventLogVariant::NftTransfer(vec![NftTransferLog {
-//                 authorized_id: Some("market.near".to_string()),
-//                 old_owner_id: "user1.near".to_string(),
-//                 new_owner_id: "user2.near".to_string(),
-//                 token_ids: vec!["token".to_string()],
-//                 memo: Some("Go Team!".to_owned()),
-//             }]),
-//         };
-//         assert_eq!(expected, log.to_string());
-//     }
-// } 
\ No newline at end of file
+use std::fmt;
+
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, serde_json, AccountId, PublicKey};
+
+use crate::review::ReviewStatus;
+
+/// Name of the event standard emitted by TicleCore.
+pub const TICLE_STANDARD_NAME: &str = "ticle";
+
+/// Version of the event payloads. Bump it whenever a log struct changes shape.
+pub const TICLE_STANDARD_VERSION: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    VapiCreate(Vec<VapiCreateLog>),
+    ReviewRequest(Vec<ReviewRequestLog>),
+    ReviewStatusUpdate(Vec<ReviewStatusUpdateLog>),
+    ReviewCancel(Vec<ReviewCancelLog>),
+    ReviewRewardClaim(Vec<ReviewRewardClaimLog>),
+    CoderEarningsClaim(Vec<CoderEarningsClaimLog>),
+    Deposit(Vec<DepositLog>),
+    Withdraw(Vec<WithdrawLog>),
+    UnbondComplete(Vec<UnbondCompleteLog>),
+    RewardClaim(Vec<RewardClaimLog>),
+    Settlement(Vec<SettlementLog>),
+    SettlementEpoch(Vec<SettlementEpochLog>),
+    UsageReport(Vec<UsageReportLog>),
+    Burn(Vec<BurnLog>),
+    TreasuryDeposit(Vec<TreasuryDepositLog>),
+    TreasuryWithdraw(Vec<TreasuryWithdrawLog>),
+    TreasuryUpdate(Vec<TreasuryUpdateLog>),
+    OwnershipTransfer(Vec<OwnershipTransferLog>),
+    SignerKeyAdd(Vec<SignerKeyLog>),
+    SignerKeyRevoke(Vec<SignerKeyLog>),
+}
+
+impl EventLogVariant {
+    /// Wraps the variant in the `ticle` standard and writes it as an `EVENT_JSON` log.
+    pub fn emit(self) {
+        let log = EventLog {
+            standard: TICLE_STANDARD_NAME.to_string(),
+            version: TICLE_STANDARD_VERSION.to_string(),
+            event: self,
+        };
+        env::log_str(&log.to_string());
+    }
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. ticle
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// An event log to capture VAPI creation
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `coder_id`: "alice.near"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VapiCreateLog {
+    pub vapi_id: String,
+    pub coder_id: AccountId,
+}
+
+/// An event log to capture a signed review request
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `coder_id`: "alice.near"
+/// * `version`: reviewed VAPI version e.g. "1.0"
+/// * `reviewer_ids`: ["bob.near", "charlie.near"]
+/// * `royalty_amounts`: ["100", "200"], in the same order as `reviewer_ids`
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReviewRequestLog {
+    pub vapi_id: String,
+    pub coder_id: AccountId,
+    pub version: String,
+    pub reviewer_ids: Vec<AccountId>,
+    pub royalty_amounts: Vec<U128>,
+}
+
+/// An event log to capture a review moving through its lifecycle
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `reviewer_id`: "bob.near"
+/// * `old_status`: "Requested"
+/// * `new_status`: "Accepted"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReviewStatusUpdateLog {
+    pub vapi_id: String,
+    pub reviewer_id: AccountId,
+    pub old_status: ReviewStatus,
+    pub new_status: ReviewStatus,
+}
+
+/// An event log to capture cancelled reviews
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `coder_id`: "alice.near"
+/// * `reviewer_ids`: ["bob.near"]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReviewCancelLog {
+    pub vapi_id: String,
+    pub coder_id: AccountId,
+    pub reviewer_ids: Vec<AccountId>,
+}
+
+/// An event log to capture a reviewer collecting their royalty
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `reviewer_id`: "bob.near"
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReviewRewardClaimLog {
+    pub vapi_id: String,
+    pub reviewer_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture a coder collecting their settlement earnings
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `coder_id`: "alice.near"
+/// * `amount`: "60"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CoderEarningsClaimLog {
+    pub vapi_id: String,
+    pub coder_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture a delegation deposit
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `account_id`: "bob.near"
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositLog {
+    pub vapi_id: String,
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture a delegation withdrawal
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `account_id`: "bob.near"
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawLog {
+    pub vapi_id: String,
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture unbonded principal being paid out
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `account_id`: "bob.near"
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnbondCompleteLog {
+    pub vapi_id: String,
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture a delegator collecting settlement rewards
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `account_id`: "bob.near"
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardClaimLog {
+    pub vapi_id: String,
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture how a settled amount was split for one VAPI
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `amount`: settled revenue
+/// * `coder_amount`: part credited to the coder
+/// * `delegator_amount`: part distributed to delegators
+/// * `burn_amount`: part burned
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SettlementLog {
+    pub vapi_id: String,
+    pub amount: U128,
+    pub coder_amount: U128,
+    pub delegator_amount: U128,
+    pub burn_amount: U128,
+}
+
+/// An event log to capture the Merkle root of an epoch's settlement being posted
+///
+/// Arguments
+/// * `epoch_id`: "2024-01"
+/// * `merkle_root`: base58 root of the epoch's `SettlementLeaf`s
+/// * `period_start`: "1704067200000000000"
+/// * `period_end`: "1706745600000000000"
+/// * `total_amount`: tokens escrowed for the epoch
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SettlementEpochLog {
+    pub epoch_id: String,
+    pub merkle_root: Base58CryptoHash,
+    pub period_start: U64,
+    pub period_end: U64,
+    pub total_amount: U128,
+}
+
+/// An event log to capture a gateway's metered usage of a VAPI being settled
+///
+/// Arguments
+/// * `gateway_id`: "gateway.near"
+/// * `vapi_id`: "alice-vapi"
+/// * `period_start`: "1704067200000000000"
+/// * `period_end`: "1706745600000000000"
+/// * `calls`: "1000"
+/// * `amount`: calls times the VAPI's usage price
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UsageReportLog {
+    pub gateway_id: AccountId,
+    pub vapi_id: String,
+    pub period_start: U64,
+    pub period_end: U64,
+    pub calls: U64,
+    pub amount: U128,
+}
+
+/// An event log to capture tokens sent to the token contract to be burned
+///
+/// Arguments
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BurnLog {
+    pub amount: U128,
+}
+
+/// An event log to capture a settlement crediting the protocol treasury
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `amount`: "5"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryDepositLog {
+    pub vapi_id: String,
+    pub amount: U128,
+}
+
+/// An event log to capture the treasury account withdrawing collected fees
+///
+/// Arguments
+/// * `treasury_id`: "treasury.near"
+/// * `amount`: "5"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryWithdrawLog {
+    pub treasury_id: AccountId,
+    pub amount: U128,
+}
+
+/// An event log to capture the owner changing where protocol fees go
+///
+/// Arguments
+/// * `old_treasury_id`: optional, "treasury.near"
+/// * `new_treasury_id`: "new-treasury.near"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryUpdateLog {
+    pub old_treasury_id: Option<AccountId>,
+    pub new_treasury_id: AccountId,
+}
+
+/// An event log to capture a VAPI changing coders
+///
+/// Arguments
+/// * `vapi_id`: "alice-vapi"
+/// * `old_coder_id`: "alice.near"
+/// * `new_coder_id`: "bob.near"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferLog {
+    pub vapi_id: String,
+    pub old_coder_id: AccountId,
+    pub new_coder_id: AccountId,
+}
+
+/// An event log to capture a review request signer key being added or retired
+///
+/// Arguments
+/// * `public_key`: "ed25519:..."
+/// * `valid_from`: optional block timestamp the key becomes valid at
+/// * `valid_until`: optional block timestamp the key stops being valid at
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignerKeyLog {
+    pub public_key: PublicKey,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<U64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<U64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_log(event: EventLogVariant) -> EventLog {
+        EventLog {
+            standard: TICLE_STANDARD_NAME.to_string(),
+            version: TICLE_STANDARD_VERSION.to_string(),
+            event,
+        }
+    }
+
+    #[test]
+    fn ticle_format_vapi_create() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"vapi_create","data":[{"vapi_id":"alice-vapi","coder_id":"alice.near"}]}"#;
+        let log = event_log(EventLogVariant::VapiCreate(vec![VapiCreateLog {
+            vapi_id: "alice-vapi".to_string(),
+            coder_id: "alice.near".parse().unwrap(),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_review_request() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"review_request","data":[{"vapi_id":"alice-vapi","coder_id":"alice.near","version":"1.0","reviewer_ids":["bob.near","charlie.near"],"royalty_amounts":["100","200"]}]}"#;
+        let log = event_log(EventLogVariant::ReviewRequest(vec![ReviewRequestLog {
+            vapi_id: "alice-vapi".to_string(),
+            coder_id: "alice.near".parse().unwrap(),
+            version: "1.0".to_string(),
+            reviewer_ids: vec!["bob.near".parse().unwrap(), "charlie.near".parse().unwrap()],
+            royalty_amounts: vec![U128(100), U128(200)],
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_review_status_update() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"review_status_update","data":[{"vapi_id":"alice-vapi","reviewer_id":"bob.near","old_status":"Submitted","new_status":"Approved"}]}"#;
+        let log = event_log(EventLogVariant::ReviewStatusUpdate(vec![ReviewStatusUpdateLog {
+            vapi_id: "alice-vapi".to_string(),
+            reviewer_id: "bob.near".parse().unwrap(),
+            old_status: ReviewStatus::Submitted,
+            new_status: ReviewStatus::Approved,
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_review_cancel() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"review_cancel","data":[{"vapi_id":"alice-vapi","coder_id":"alice.near","reviewer_ids":["bob.near"]}]}"#;
+        let log = event_log(EventLogVariant::ReviewCancel(vec![ReviewCancelLog {
+            vapi_id: "alice-vapi".to_string(),
+            coder_id: "alice.near".parse().unwrap(),
+            reviewer_ids: vec!["bob.near".parse().unwrap()],
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_review_reward_claim() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"review_reward_claim","data":[{"vapi_id":"alice-vapi","reviewer_id":"bob.near","amount":"100"}]}"#;
+        let log = event_log(EventLogVariant::ReviewRewardClaim(vec![ReviewRewardClaimLog {
+            vapi_id: "alice-vapi".to_string(),
+            reviewer_id: "bob.near".parse().unwrap(),
+            amount: U128(100),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_coder_earnings_claim() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"coder_earnings_claim","data":[{"vapi_id":"alice-vapi","coder_id":"alice.near","amount":"60"}]}"#;
+        let log = event_log(EventLogVariant::CoderEarningsClaim(vec![CoderEarningsClaimLog {
+            vapi_id: "alice-vapi".to_string(),
+            coder_id: "alice.near".parse().unwrap(),
+            amount: U128(60),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_deposit_and_withdraw() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"deposit","data":[{"vapi_id":"alice-vapi","account_id":"bob.near","amount":"100"}]}"#;
+        let log = event_log(EventLogVariant::Deposit(vec![DepositLog {
+            vapi_id: "alice-vapi".to_string(),
+            account_id: "bob.near".parse().unwrap(),
+            amount: U128(100),
+        }]));
+        assert_eq!(expected, log.to_string());
+
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"withdraw","data":[{"vapi_id":"alice-vapi","account_id":"bob.near","amount":"100"}]}"#;
+        let log = event_log(EventLogVariant::Withdraw(vec![WithdrawLog {
+            vapi_id: "alice-vapi".to_string(),
+            account_id: "bob.near".parse().unwrap(),
+            amount: U128(100),
+        }]));
+        assert_eq!(expected, log.to_string());
+
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"unbond_complete","data":[{"vapi_id":"alice-vapi","account_id":"bob.near","amount":"100"}]}"#;
+        let log = event_log(EventLogVariant::UnbondComplete(vec![UnbondCompleteLog {
+            vapi_id: "alice-vapi".to_string(),
+            account_id: "bob.near".parse().unwrap(),
+            amount: U128(100),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_reward_claim() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"reward_claim","data":[{"vapi_id":"alice-vapi","account_id":"bob.near","amount":"39"}]}"#;
+        let log = event_log(EventLogVariant::RewardClaim(vec![RewardClaimLog {
+            vapi_id: "alice-vapi".to_string(),
+            account_id: "bob.near".parse().unwrap(),
+            amount: U128(39),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_settlement_vector() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"settlement","data":[{"vapi_id":"alice-vapi","amount":"100","coder_amount":"60","delegator_amount":"39","burn_amount":"1"},{"vapi_id":"bob-vapi","amount":"200","coder_amount":"120","delegator_amount":"78","burn_amount":"2"}]}"#;
+        let log = event_log(EventLogVariant::Settlement(vec![
+            SettlementLog {
+                vapi_id: "alice-vapi".to_string(),
+                amount: U128(100),
+                coder_amount: U128(60),
+                delegator_amount: U128(39),
+                burn_amount: U128(1),
+            },
+            SettlementLog {
+                vapi_id: "bob-vapi".to_string(),
+                amount: U128(200),
+                coder_amount: U128(120),
+                delegator_amount: U128(78),
+                burn_amount: U128(2),
+            },
+        ]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_settlement_epoch() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"settlement_epoch","data":[{"epoch_id":"2024-01","merkle_root":"11111111111111111111111111111111","period_start":"1","period_end":"2","total_amount":"100"}]}"#;
+        let log = event_log(EventLogVariant::SettlementEpoch(vec![SettlementEpochLog {
+            epoch_id: "2024-01".to_string(),
+            merkle_root: Base58CryptoHash::from([0; 32]),
+            period_start: U64(1),
+            period_end: U64(2),
+            total_amount: U128(100),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_usage_report() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"usage_report","data":[{"gateway_id":"gateway.near","vapi_id":"alice-vapi","period_start":"1","period_end":"2","calls":"1000","amount":"5000"}]}"#;
+        let log = event_log(EventLogVariant::UsageReport(vec![UsageReportLog {
+            gateway_id: "gateway.near".parse().unwrap(),
+            vapi_id: "alice-vapi".to_string(),
+            period_start: U64(1),
+            period_end: U64(2),
+            calls: U64(1000),
+            amount: U128(5000),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_burn() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"burn","data":[{"amount":"3"}]}"#;
+        let log = event_log(EventLogVariant::Burn(vec![BurnLog { amount: U128(3) }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_treasury() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"treasury_deposit","data":[{"vapi_id":"alice-vapi","amount":"5"}]}"#;
+        let log = event_log(EventLogVariant::TreasuryDeposit(vec![TreasuryDepositLog {
+            vapi_id: "alice-vapi".to_string(),
+            amount: U128(5),
+        }]));
+        assert_eq!(expected, log.to_string());
+
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"treasury_withdraw","data":[{"treasury_id":"treasury.near","amount":"5"}]}"#;
+        let log = event_log(EventLogVariant::TreasuryWithdraw(vec![TreasuryWithdrawLog {
+            treasury_id: "treasury.near".parse().unwrap(),
+            amount: U128(5),
+        }]));
+        assert_eq!(expected, log.to_string());
+
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"treasury_update","data":[{"old_treasury_id":null,"new_treasury_id":"treasury.near"}]}"#;
+        let log = event_log(EventLogVariant::TreasuryUpdate(vec![TreasuryUpdateLog {
+            old_treasury_id: None,
+            new_treasury_id: "treasury.near".parse().unwrap(),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_ownership_transfer() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"ownership_transfer","data":[{"vapi_id":"alice-vapi","old_coder_id":"alice.near","new_coder_id":"bob.near"}]}"#;
+        let log = event_log(EventLogVariant::OwnershipTransfer(vec![OwnershipTransferLog {
+            vapi_id: "alice-vapi".to_string(),
+            old_coder_id: "alice.near".parse().unwrap(),
+            new_coder_id: "bob.near".parse().unwrap(),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn ticle_format_signer_key_add() {
+        let expected = r#"EVENT_JSON:{"standard":"ticle","version":"1.0.0","event":"signer_key_add","data":[{"public_key":"ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp","valid_until":"1000"}]}"#;
+        let log = event_log(EventLogVariant::SignerKeyAdd(vec![SignerKeyLog {
+            public_key: "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp".parse().unwrap(),
+            valid_from: None,
+            valid_until: Some(U64(1000)),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::*;
+
+/// Basis points that make up a whole settlement.
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Most of a settlement that may be burned.
+pub const MAX_BURN_BPS: u16 = 1_000;
+
+/// Most of a settlement that may go to the protocol treasury.
+pub const MAX_TREASURY_BPS: u16 = 2_000;
+
+/// How a settlement is split, in basis points. The coder gets whatever is left.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeConfig {
+    pub delegator_bps: u16,
+    pub burn_bps: u16,
+    /// Highest delegator share a coder may offer on their VAPI with `set_vapi_delegator_bps`.
+    pub max_delegator_bps: u16,
+    #[serde(default)]
+    pub treasury_bps: u16,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            delegator_bps: 3_900,
+            burn_bps: 100,
+            max_delegator_bps: 9_000,
+            treasury_bps: 0,
+        }
+    }
+}
+
+impl FeeConfig {
+    fn assert_valid(&self) {
+        require!(self.burn_bps <= MAX_BURN_BPS, "burn_bps is too high");
+        require!(self.treasury_bps <= MAX_TREASURY_BPS, "treasury_bps is too high");
+        require!(self.delegator_bps <= self.max_delegator_bps, "delegator_bps can't exceed max_delegator_bps");
+        require!(self.max_delegator_bps + self.burn_bps + self.treasury_bps <= BPS_DENOMINATOR, "Fees can't exceed 100%");
+    }
+}
+
+/// Fee split that applies to one VAPI.
+#[near(serializers = [json])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeSchedule {
+    pub coder_bps: u16,
+    pub delegator_bps: u16,
+    pub burn_bps: u16,
+    pub treasury_bps: u16,
+}
+
+/// One settled amount split according to a `FeeSchedule`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeSplit {
+    pub coder_amount: Balance,
+    pub delegator_amount: Balance,
+    pub burn_amount: Balance,
+    pub treasury_amount: Balance,
+}
+
+impl FeeSchedule {
+    /// Rounding dust goes to the coder.
+    pub fn split(&self, amount: Balance) -> FeeSplit {
+        let delegator_amount = amount * self.delegator_bps as u128 / BPS_DENOMINATOR as u128;
+        let burn_amount = amount * self.burn_bps as u128 / BPS_DENOMINATOR as u128;
+        let treasury_amount = amount * self.treasury_bps as u128 / BPS_DENOMINATOR as u128;
+        return FeeSplit {
+            coder_amount: amount - delegator_amount - burn_amount - treasury_amount,
+            delegator_amount,
+            burn_amount,
+            treasury_amount,
+        };
+    }
+}
+
+#[near]
+impl TicleCore {
+    pub fn set_fee_config(&mut self, fee_config: FeeConfig) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can set the fee config");
+        fee_config.assert_valid();
+        self.fee_config = fee_config;
+        log!("[set_fee_config] {:?}", fee_config);
+    }
+
+    /// Lets the coder give delegators of `vapi_id` a larger share than the protocol default,
+    /// up to `max_delegator_bps`. `None` goes back to the default.
+    pub fn set_vapi_delegator_bps(&mut self, vapi_id: String, delegator_bps: Option<u16>) {
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        require!(vapi.coder_info.account_id == env::predecessor_account_id(), "Only coder can set the delegator share");
+        if let Some(delegator_bps) = delegator_bps {
+            require!(delegator_bps >= self.fee_config.delegator_bps, "delegator_bps can't be lower than the protocol default");
+            require!(delegator_bps <= self.fee_config.max_delegator_bps, "delegator_bps can't exceed max_delegator_bps");
+        }
+
+        vapi.delegator_bps = delegator_bps;
+        self.vapis.insert(&vapi_id, &vapi);
+        log!("[set_vapi_delegator_bps] {}: {:?}", vapi_id, delegator_bps);
+    }
+
+    pub fn get_fee_config(&self) -> FeeConfig {
+        return self.fee_config;
+    }
+
+    pub fn get_fee_schedule(&self, vapi_id: String) -> FeeSchedule {
+        return self.internal_fee_schedule(&self.internal_get_vapi(&vapi_id));
+    }
+}
+
+impl TicleCore {
+    /// The VAPI override is clamped to the current protocol limits, which may have
+    /// changed since the coder set it.
+    pub(crate) fn internal_fee_schedule(&self, vapi: &VAPI) -> FeeSchedule {
+        let fee_config = &self.fee_config;
+        let delegator_bps = vapi.delegator_bps
+            .map_or(fee_config.delegator_bps, |bps| bps.clamp(fee_config.delegator_bps, fee_config.max_delegator_bps));
+
+        return FeeSchedule {
+            coder_bps: BPS_DENOMINATOR - delegator_bps - fee_config.burn_bps - fee_config.treasury_bps,
+            delegator_bps,
+            burn_bps: fee_config.burn_bps,
+            treasury_bps: fee_config.treasury_bps,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_split_matches_previous_hard_coded_fees() {
+        let fee_config = FeeConfig::default();
+        let schedule = FeeSchedule {
+            coder_bps: BPS_DENOMINATOR - fee_config.delegator_bps - fee_config.burn_bps - fee_config.treasury_bps,
+            delegator_bps: fee_config.delegator_bps,
+            burn_bps: fee_config.burn_bps,
+            treasury_bps: fee_config.treasury_bps,
+        };
+
+        for amount in [0u128, 1, 99, 100, 12_345, 10_000_000_000_000_000_000_000_000] {
+            let delegator_amount = amount * 39 / 100;
+            let burn_amount = amount / 100;
+            assert_eq!(schedule.split(amount), FeeSplit {
+                coder_amount: amount - delegator_amount - burn_amount,
+                delegator_amount,
+                burn_amount,
+                treasury_amount: 0,
+            });
+        }
+    }
+
+    #[test]
+    fn split_gives_rounding_dust_to_coder() {
+        let schedule = FeeSchedule { coder_bps: 4_000, delegator_bps: 3_333, burn_bps: 1_667, treasury_bps: 1_000 };
+        let split = schedule.split(7);
+        assert_eq!((split.delegator_amount, split.burn_amount, split.treasury_amount), (2, 1, 0));
+        assert_eq!(split.coder_amount, 4);
+    }
+}
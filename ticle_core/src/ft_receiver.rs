@@ -1,25 +1,50 @@
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::json_types::Base58CryptoHash;
 
 use crate::*;
 
+/// `msg` of an `ft_transfer_call` to TicleCore, e.g.
+/// `{"action": "deposit", "vapi_id": "..."}`. The `action` tag picks the variant so a
+/// message missing a field is rejected instead of being read as a shorter variant.
+///
+/// Messages used to be untagged; `{"vapi_id": "..."}` without `action` is now refunded.
+/// The README lists the fields of every action.
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
-#[serde(untagged)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
 enum TokenReceiverMessage {
     RequestReview {
         vapi_id: String,
         version: String,
         reviewer_ids: Vec<AccountId>,
         royalty_amounts: Vec<U128>,
-        signature: String,
+        nonce: U64,
+        expires_at: U64,
+        // 서명이 하나뿐이면 signature, 여러 서명자가 필요한 경우 signatures를 사용한다.
+        signature: Option<String>,
+        #[serde(default)]
+        signatures: Vec<String>,
     },
     Settlement {
+        batch_id: String,
+        period_start: U64,
+        period_end: U64,
         vapi_ids: Vec<String>,
         amounts: Vec<U128>,
     },
+    SettlementEpoch {
+        epoch_id: String,
+        period_start: U64,
+        period_end: U64,
+        merkle_root: Base58CryptoHash,
+    },
     Deposit {
         vapi_id: String,
     },
+    FundGateway {
+        gateway_id: AccountId,
+        vapi_id: String,
+    },
 }
 
 #[near]
@@ -41,14 +66,94 @@ impl FungibleTokenReceiver for TicleCore {
             TokenReceiverMessage::Deposit { vapi_id } => {
                 self.internal_deposit(&sender_id, vapi_id, amount.into());
             }
-            TokenReceiverMessage::Settlement { vapi_ids, amounts } => {
-                self.internal_settlement(&sender_id, vapi_ids, amounts);
+            TokenReceiverMessage::Settlement { batch_id, period_start, period_end, vapi_ids, amounts } => {
+                require!(vapi_ids.len() == amounts.len(), "vapi_ids and amounts must have the same length");
+                let entries = vapi_ids.into_iter().zip(amounts).collect();
+                let batch_id = self.internal_register_settlement(&sender_id, batch_id, period_start.0, period_end.0, entries, amount);
+                // 첫 페이지는 바로 적용하고, 나머지는 process_settlement로 나눠서 처리한다.
+                self.internal_process_settlement(&batch_id, SETTLEMENT_PAGE_SIZE);
+            }
+            TokenReceiverMessage::FundGateway { gateway_id, vapi_id } => {
+                self.internal_fund_gateway(gateway_id, vapi_id, amount);
             }
-            TokenReceiverMessage::RequestReview { vapi_id, version, reviewer_ids, royalty_amounts, signature } => {
-                self.internal_request_review(vapi_id, version, reviewer_ids, royalty_amounts, sender_id, amount.into(), signature);
+            TokenReceiverMessage::SettlementEpoch { epoch_id, period_start, period_end, merkle_root } => {
+                self.internal_post_settlement_epoch(&sender_id, epoch_id, period_start.0, period_end.0, merkle_root.into(), amount);
+            }
+            TokenReceiverMessage::RequestReview { vapi_id, version, reviewer_ids, royalty_amounts, nonce, expires_at, signature, signatures } => {
+                let payload = ReviewRequestPayload {
+                    contract_id: env::current_account_id(),
+                    vapi_id,
+                    vapi_version: version,
+                    coder_id: sender_id,
+                    reviewer_ids,
+                    royalty_amounts: royalty_amounts.into_iter().map(|amount| amount.0).collect(),
+                    nonce: nonce.0,
+                    expires_at: expires_at.0,
+                };
+                let signatures: Vec<String> = signature.into_iter().chain(signatures).collect();
+                self.internal_request_review(payload, amount, signatures);
             }
         }
 
         return PromiseOrValue::Value(U128(0));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(msg: &str) -> TokenReceiverMessage {
+        return serde_json::from_str::<TokenReceiverMessage>(msg).unwrap();
+    }
+
+    #[test]
+    fn action_selects_the_message() {
+        let message = parse(r#"{"action": "deposit", "vapi_id": "vapi"}"#);
+        assert!(matches!(message, TokenReceiverMessage::Deposit { vapi_id } if vapi_id == "vapi"));
+    }
+
+    #[test]
+    fn settlement_messages_are_tagged() {
+        let message = parse(r#"{"action": "settlement", "batch_id": "2024-01", "period_start": "0", "period_end": "10", "vapi_ids": ["vapi"], "amounts": ["100"]}"#);
+        assert!(matches!(message, TokenReceiverMessage::Settlement { batch_id, amounts, .. } if batch_id == "2024-01" && amounts[0].0 == 100));
+
+        let message = parse(r#"{"action": "settlement_epoch", "epoch_id": "2024-01", "period_start": "0", "period_end": "10", "merkle_root": "11111111111111111111111111111111"}"#);
+        assert!(matches!(message, TokenReceiverMessage::SettlementEpoch { epoch_id, period_end, .. } if epoch_id == "2024-01" && period_end.0 == 10));
+    }
+
+    #[test]
+    fn fund_gateway_message_is_tagged() {
+        let message = parse(r#"{"action": "fund_gateway", "gateway_id": "gateway.near", "vapi_id": "vapi"}"#);
+        assert!(matches!(message, TokenReceiverMessage::FundGateway { gateway_id, vapi_id } if gateway_id.as_str() == "gateway.near" && vapi_id == "vapi"));
+    }
+
+    #[test]
+    fn review_request_takes_one_or_many_signatures() {
+        let message = parse(r#"{"action": "request_review", "vapi_id": "vapi", "version": "1.0", "reviewer_ids": ["bob.near"], "royalty_amounts": ["10"], "nonce": "0", "expires_at": "10", "signature": "ed25519:sig"}"#);
+        assert!(matches!(message, TokenReceiverMessage::RequestReview { signature: Some(_), signatures, .. } if signatures.is_empty()));
+
+        let message = parse(r#"{"action": "request_review", "vapi_id": "vapi", "version": "1.0", "reviewer_ids": ["bob.near"], "royalty_amounts": ["10"], "nonce": "0", "expires_at": "10", "signatures": ["ed25519:a", "ed25519:b"]}"#);
+        assert!(matches!(message, TokenReceiverMessage::RequestReview { signature: None, signatures, .. } if signatures.len() == 2));
+    }
+
+    #[test]
+    fn untagged_messages_are_rejected() {
+        // action 필드가 생기기 전의 형식
+        assert!(serde_json::from_str::<TokenReceiverMessage>(r#"{"vapi_id": "vapi"}"#).is_err());
+        assert!(serde_json::from_str::<TokenReceiverMessage>(r#"{"gateway_id": "gateway.near", "vapi_id": "vapi"}"#).is_err());
+    }
+
+    #[test]
+    fn incomplete_review_request_is_not_a_deposit() {
+        // nonce와 expires_at이 빠진 리뷰 요청
+        let msg = r#"{"action": "request_review", "vapi_id": "vapi", "version": "1.0", "reviewer_ids": ["bob.near"], "royalty_amounts": ["10"]}"#;
+        assert!(serde_json::from_str::<TokenReceiverMessage>(msg).is_err());
+
+        let untagged = r#"{"vapi_id": "vapi", "version": "1.0", "reviewer_ids": ["bob.near"], "royalty_amounts": ["10"]}"#;
+        assert!(serde_json::from_str::<TokenReceiverMessage>(untagged).is_err());
+
+        let extra_fields = r#"{"action": "deposit", "vapi_id": "vapi", "version": "1.0"}"#;
+        assert!(serde_json::from_str::<TokenReceiverMessage>(extra_fields).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::*;
+use crate::payload::UsageReportPayload;
+use ticle_signing::verify_signature;
+
+/// An API gateway allowed to report metered usage. Its pools hold the revenue the
+/// gateway collected from API consumers, one pool per VAPI; reports are paid out of
+/// the pool of their VAPI only.
+#[near(serializers = [borsh])]
+pub struct Gateway {
+    /// `None` once the owner removed the gateway. The pools can still be withdrawn.
+    public_key: Option<PublicKey>,
+    /// Sum of every VAPI pool of the gateway.
+    balance: Balance,
+    total_settled: Balance,
+}
+
+/// The signed fields of a usage report as submitted. The contract account is added
+/// when the payload is rebuilt, so it isn't part of the arguments.
+#[near(serializers = [json])]
+pub struct UsageReport {
+    pub gateway_id: AccountId,
+    pub vapi_id: String,
+    pub period_start: U64,
+    pub period_end: U64,
+    pub calls: U64,
+    pub price: U128,
+}
+
+#[near(serializers = [json])]
+pub struct GatewayView {
+    gateway_id: AccountId,
+    public_key: Option<PublicKey>,
+    balance: U128,
+    total_settled: U128,
+}
+
+#[near]
+impl TicleCore {
+    /// Registers `gateway_id` or rotates its key.
+    pub fn set_gateway_key(&mut self, gateway_id: AccountId, public_key: PublicKey) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can manage gateways");
+        require!(public_key.curve_type() == CurveType::ED25519, "Only ed25519 gateway keys are supported");
+
+        let mut gateway = self.gateways.get(&gateway_id).unwrap_or(Gateway {
+            public_key: None,
+            balance: 0,
+            total_settled: 0,
+        });
+        gateway.public_key = Some(public_key);
+        self.gateways.insert(&gateway_id, &gateway);
+        log!("[set_gateway_key] {}", gateway_id);
+    }
+
+    /// Stops accepting reports from `gateway_id`.
+    pub fn remove_gateway(&mut self, gateway_id: AccountId) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can manage gateways");
+        let mut gateway = self.gateways.get(&gateway_id).expect("Gateway not found");
+        gateway.public_key = None;
+        self.gateways.insert(&gateway_id, &gateway);
+        log!("[remove_gateway] {}", gateway_id);
+    }
+
+    /// Price of one call of `vapi_id` in tokens. Reports are priced when they are
+    /// submitted, so a new price also applies to periods not reported yet.
+    pub fn set_vapi_usage_price(&mut self, vapi_id: String, price: U128) {
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        require!(vapi.coder_info.account_id == env::predecessor_account_id(), "Only coder can set the usage price");
+
+        vapi.usage_price = price.0;
+        self.vapis.insert(&vapi_id, &vapi);
+        log!("[set_vapi_usage_price] {}: {}", vapi_id, price.0);
+    }
+
+    /// Settles the calls a gateway served for one VAPI, paid from the gateway's pool for
+    /// that VAPI. Anyone may submit it; `signature` must be the gateway key's signature
+    /// of the report's `UsageReportPayload::hash()`, which commits to the per-call price.
+    /// Periods of one gateway and VAPI must follow each other.
+    pub fn submit_usage_report(&mut self, report: UsageReport, signature: String) -> PromiseOrValue<()> {
+        let UsageReport { gateway_id, vapi_id, period_start, period_end, calls, price } = report;
+        let mut gateway = self.gateways.get(&gateway_id).expect("Gateway not found");
+        let public_key = gateway.public_key.clone().expect("Gateway was removed");
+        require!(period_start.0 < period_end.0, "period_start must be earlier than period_end");
+        require!(period_end.0 <= env::block_timestamp(), "Usage period has not ended yet");
+
+        let payload = UsageReportPayload {
+            contract_id: env::current_account_id(),
+            gateway_id: gateway_id.clone(),
+            vapi_id: vapi_id.clone(),
+            period_start: period_start.0,
+            period_end: period_end.0,
+            calls: calls.0,
+            price: price.0,
+        };
+        require!(verify_signature(&payload.hash(), &signature, &public_key), "Invalid signature");
+
+        let report_key = (gateway_id.clone(), vapi_id.clone());
+        let reported_until = self.usage_reported_until.get(&report_key).unwrap_or(0);
+        require!(period_start.0 >= reported_until, "Usage period overlaps the previous report");
+        self.usage_reported_until.insert(&report_key, &period_end.0);
+
+        // 코더가 서명 이후에 가격을 바꿨다면 gateway가 새 가격으로 다시 서명해야 한다.
+        let vapi = self.internal_get_vapi(&vapi_id);
+        require!(vapi.usage_price > 0, "VAPI has no usage price");
+        require!(price.0 == vapi.usage_price, "Usage price changed since the report was signed");
+        let amount = price.0.checked_mul(calls.0 as u128).expect("Usage amount overflow");
+
+        let pool_balance = self.usage_pools.get(&report_key).unwrap_or(0);
+        require!(amount <= pool_balance, "Not enough balance in the gateway pool of the VAPI");
+        self.usage_pools.insert(&report_key, &(pool_balance - amount));
+        gateway.balance -= amount;
+        gateway.total_settled += amount;
+        self.gateways.insert(&gateway_id, &gateway);
+
+        EventLogVariant::UsageReport(vec![UsageReportLog {
+            gateway_id: gateway_id.clone(),
+            vapi_id: vapi_id.clone(),
+            period_start,
+            period_end,
+            calls,
+            amount: U128(amount),
+        }]).emit();
+
+        if amount == 0 {
+            return PromiseOrValue::Value(());
+        }
+        return self.internal_apply_settlement(vapi_id, &format!("usage:{}", gateway_id), period_start.0, period_end.0, amount);
+    }
+
+    /// Sends unspent funds of the caller's pool for `vapi_id` back to the gateway account.
+    /// Withdraws the whole pool when `amount` is omitted.
+    pub fn withdraw_gateway_pool(&mut self, vapi_id: String, amount: Option<U128>) -> Promise {
+        let gateway_id = env::predecessor_account_id();
+        let mut gateway = self.gateways.get(&gateway_id).expect("Gateway not found");
+        let pool_key = (gateway_id.clone(), vapi_id.clone());
+        let pool_balance = self.usage_pools.get(&pool_key).unwrap_or(0);
+
+        let amount: Balance = amount.map(|amount| amount.0).unwrap_or(pool_balance);
+        require!(amount > 0, "Nothing to withdraw");
+        require!(amount <= pool_balance, "Not enough balance in the gateway pool of the VAPI");
+        self.usage_pools.insert(&pool_key, &(pool_balance - amount));
+        gateway.balance -= amount;
+        self.gateways.insert(&gateway_id, &gateway);
+
+        return ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(20))
+            .ft_transfer(gateway_id.clone(), U128(amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(20))
+                    .callback_withdraw_gateway_pool(gateway_id, vapi_id, U128(amount))
+            );
+    }
+
+    #[private]
+    pub fn callback_withdraw_gateway_pool(&mut self, gateway_id: AccountId, vapi_id: String, amount: U128) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        log!("[callback_withdraw_gateway_pool] ft_transfer failed, restoring {}", amount.0);
+        self.internal_add_to_usage_pool(gateway_id, vapi_id, amount.0);
+    }
+
+    pub fn get_gateway(&self, gateway_id: AccountId) -> Option<GatewayView> {
+        return self.gateways.get(&gateway_id).map(|gateway| GatewayView {
+            gateway_id,
+            public_key: gateway.public_key,
+            balance: U128(gateway.balance),
+            total_settled: U128(gateway.total_settled),
+        });
+    }
+
+    pub fn get_gateways(&self) -> Vec<GatewayView> {
+        return self.gateways
+            .iter()
+            .map(|(gateway_id, gateway)| GatewayView {
+                gateway_id,
+                public_key: gateway.public_key,
+                balance: U128(gateway.balance),
+                total_settled: U128(gateway.total_settled),
+            })
+            .collect();
+    }
+
+    pub fn get_vapi_usage_price(&self, vapi_id: String) -> U128 {
+        return U128(self.internal_get_vapi(&vapi_id).usage_price);
+    }
+
+    /// Balance of the pool `gateway_id` holds for `vapi_id`.
+    pub fn get_gateway_pool(&self, gateway_id: AccountId, vapi_id: String) -> U128 {
+        return U128(self.usage_pools.get(&(gateway_id, vapi_id)).unwrap_or(0));
+    }
+
+    /// End of the last period `gateway_id` reported for `vapi_id`.
+    pub fn get_usage_reported_until(&self, gateway_id: AccountId, vapi_id: String) -> U64 {
+        return U64(self.usage_reported_until.get(&(gateway_id, vapi_id)).unwrap_or(0));
+    }
+}
+
+impl TicleCore {
+    /// Adds tokens sent with `ft_transfer_call` to a gateway's pool for `vapi_id`.
+    /// Anyone may fund it.
+    pub(crate) fn internal_fund_gateway(&mut self, gateway_id: AccountId, vapi_id: String, amount: U128) {
+        let gateway = self.gateways.get(&gateway_id).expect("Gateway not found");
+        require!(gateway.public_key.is_some(), "Gateway was removed");
+        self.internal_get_vapi(&vapi_id);
+        self.internal_add_to_usage_pool(gateway_id.clone(), vapi_id.clone(), amount.0);
+        log!("[internal_fund_gateway] {} for {}: {}", gateway_id, vapi_id, amount.0);
+    }
+
+    fn internal_add_to_usage_pool(&mut self, gateway_id: AccountId, vapi_id: String, amount: Balance) {
+        let mut gateway = self.gateways.get(&gateway_id).expect("Gateway not found");
+        gateway.balance += amount;
+        self.gateways.insert(&gateway_id, &gateway);
+
+        let pool_key = (gateway_id, vapi_id);
+        let pool_balance = self.usage_pools.get(&pool_key).unwrap_or(0);
+        self.usage_pools.insert(&pool_key, &(pool_balance + amount));
+    }
+}
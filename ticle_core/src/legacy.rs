@@ -0,0 +1,197 @@
+//! State layouts written before every VAPI got its own storage namespace.
+//!
+//! Until then every VAPI was created with `UnorderedMap::new(b"r")` for its reviewers
+//! and `LookupMap::new(b"d")` for its delegators, so all VAPIs read and wrote the same
+//! trie keys. These types are only used by `TicleCore::migrate` and
+//! `TicleCore::migrate_vapi_storage`.
+
+use near_sdk::borsh::{self, BorshDeserialize};
+
+use crate::*;
+
+const LEGACY_VAPIS_PREFIX: &[u8] = b"v";
+const LEGACY_REVIEWER_INFOS_PREFIX: &[u8] = b"r";
+const LEGACY_DELEGATOR_INFOS_PREFIX: &[u8] = b"d";
+
+#[near(serializers = [borsh])]
+pub struct LegacyTicleCore {
+    pub vapis: LookupMap<String, LegacyVAPI>,
+    pub token_id: AccountId,
+    pub owner_id: AccountId,
+    pub signer_public_key: Vec<u8>,
+}
+
+#[near(serializers = [borsh])]
+pub struct LegacyVAPI {
+    pub coder_info: LegacyCoderInfo,
+    pub reviewer_infos: UnorderedMap<AccountId, LegacyReviewerInfo>,
+    pub delegation_info: LegacyDelegationInfo,
+}
+
+#[near(serializers = [borsh])]
+pub struct LegacyCoderInfo {
+    pub account_id: AccountId,
+    pub unclaimed_reward_amount: Balance,
+}
+
+#[near(serializers = [borsh])]
+pub struct LegacyDelegationInfo {
+    pub delegator_infos: LookupMap<AccountId, Delegator>,
+    pub total_deposit_amount: Balance,
+    pub acc_reward_per_share: Balance,
+}
+
+#[near(serializers = [borsh])]
+pub struct LegacyReviewerInfo {
+    pub version: String,
+    pub royalty_amount: Balance,
+    pub timestamp: u64,
+}
+
+/// Removes a VAPI from the legacy `b"v"` map and returns it. The migrated VAPI is
+/// written under the same key, and `LookupMap::insert` would fail to decode the legacy
+/// value it replaces.
+pub fn take_vapi(vapi_id: &String) -> Option<LegacyVAPI> {
+    let key = [LEGACY_VAPIS_PREFIX, borsh::to_vec(vapi_id).unwrap().as_slice()].concat();
+    let vapi = env::storage_read(&key).map(|bytes| LegacyVAPI::try_from_slice(&bytes).expect("Invalid legacy VAPI"))?;
+    env::storage_remove(&key);
+    return Some(vapi);
+}
+
+/// Removes a reviewer entry from the shared `b"r"` map and returns it.
+///
+/// Every legacy VAPI kept its own copy of the map's length, so going through
+/// `UnorderedMap::remove` on a stale copy can miss or clobber entries written by another
+/// VAPI. The raw layout is `<prefix>i<key> -> index`, `<prefix>k<index> -> key` and
+/// `<prefix>v<index> -> value`; all three are removed so the entry can be moved only once.
+pub fn take_reviewer_info(reviewer_id: &AccountId) -> Option<ReviewerInfo> {
+    let index_key = [LEGACY_REVIEWER_INFOS_PREFIX, b"i".as_slice(), borsh::to_vec(reviewer_id).unwrap().as_slice()].concat();
+    let index = env::storage_read(&index_key)?;
+    let index = u64::try_from_slice(&index).expect("Invalid legacy reviewer index");
+
+    let value_key = [LEGACY_REVIEWER_INFOS_PREFIX, b"v".as_slice(), index.to_le_bytes().as_slice()].concat();
+    let value = env::storage_read(&value_key)?;
+    let legacy_info = LegacyReviewerInfo::try_from_slice(&value).expect("Invalid legacy reviewer info");
+
+    let key_key = [LEGACY_REVIEWER_INFOS_PREFIX, b"k".as_slice(), index.to_le_bytes().as_slice()].concat();
+    env::storage_remove(&index_key);
+    env::storage_remove(&key_key);
+    env::storage_remove(&value_key);
+
+    // 예전에는 요청 후 2주가 지나면 바로 수령할 수 있었으므로, 제출된 리뷰로 취급해
+    // 승인 대기 기간이 요청 시점부터 흐르도록 한다.
+    return Some(ReviewerInfo {
+        version: legacy_info.version,
+        royalty_amount: legacy_info.royalty_amount,
+        timestamp: legacy_info.timestamp,
+        status: ReviewStatus::Submitted,
+        updated_at: legacy_info.timestamp,
+    });
+}
+
+/// Removes a delegator entry from the shared `b"d"` map and returns it.
+pub fn take_delegator(delegator_id: &AccountId) -> Option<Delegator> {
+    let key = [LEGACY_DELEGATOR_INFOS_PREFIX, borsh::to_vec(delegator_id).unwrap().as_slice()].concat();
+    let delegator = env::storage_read(&key).map(|bytes| Delegator::try_from_slice(&bytes).expect("Invalid legacy delegator"))?;
+    env::storage_remove(&key);
+    return Some(delegator);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const VAPI_A: &str = "vapi-a";
+    const VAPI_B: &str = "vapi-b";
+
+    /// Writes a pre-upgrade state in which `vapi-a` (150 deposited) and `vapi-b`
+    /// (100 deposited) share the `b"r"`/`b"d"` maps, and runs `migrate` as the owner.
+    fn migrated_contract() -> TicleCore {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(5))
+            .predecessor_account_id(accounts(0))
+            .build());
+
+        let mut reviewer_infos = UnorderedMap::new(LEGACY_REVIEWER_INFOS_PREFIX.to_vec());
+        reviewer_infos.insert(&accounts(3), &LegacyReviewerInfo { version: "1.0".to_string(), royalty_amount: 10, timestamp: 1 });
+        let mut delegator_infos = LookupMap::new(LEGACY_DELEGATOR_INFOS_PREFIX.to_vec());
+        delegator_infos.insert(&accounts(3), &Delegator { deposit_amount: 100, reward_debt: 0 });
+        delegator_infos.insert(&accounts(4), &Delegator { deposit_amount: 50, reward_debt: 0 });
+
+        let mut vapis = LookupMap::new(LEGACY_VAPIS_PREFIX.to_vec());
+        for (vapi_id, coder, total_deposit_amount) in [(VAPI_A, 1, 150), (VAPI_B, 2, 100)] {
+            vapis.insert(&vapi_id.to_string(), &LegacyVAPI {
+                coder_info: LegacyCoderInfo { account_id: accounts(coder), unclaimed_reward_amount: 0 },
+                reviewer_infos: UnorderedMap::new(LEGACY_REVIEWER_INFOS_PREFIX.to_vec()),
+                delegation_info: LegacyDelegationInfo {
+                    delegator_infos: LookupMap::new(LEGACY_DELEGATOR_INFOS_PREFIX.to_vec()),
+                    total_deposit_amount,
+                    acc_reward_per_share: 0,
+                },
+            });
+        }
+        env::state_write(&LegacyTicleCore {
+            vapis,
+            token_id: "token.near".parse().unwrap(),
+            owner_id: accounts(0),
+            signer_public_key: vec![1; 32],
+        });
+
+        return TicleCore::migrate(vec![VAPI_A.to_string(), VAPI_B.to_string()]);
+    }
+
+    #[test]
+    fn moves_each_entry_into_one_vapi() {
+        let mut contract = migrated_contract();
+        contract.migrate_vapi_storage(VAPI_A.to_string(), vec![accounts(3)], vec![accounts(3)]);
+        contract.migrate_vapi_storage(VAPI_B.to_string(), vec![], vec![accounts(4)]);
+
+        let vapi_a = contract.internal_get_vapi(&VAPI_A.to_string());
+        assert_eq!(vapi_a.delegation_info.delegator_infos.get(&accounts(3)).unwrap().deposit_amount, 100);
+        assert_eq!(vapi_a.delegation_info.total_deposit_amount, 100);
+        assert_eq!(vapi_a.reviewer_infos.get(&accounts(3)).unwrap().status, ReviewStatus::Submitted);
+
+        let vapi_b = contract.internal_get_vapi(&VAPI_B.to_string());
+        assert!(vapi_b.delegation_info.delegator_infos.get(&accounts(3)).is_none());
+        assert_eq!(vapi_b.delegation_info.total_deposit_amount, 50);
+
+        // 옮겨진 항목은 공용 map에서 지워진다.
+        assert!(take_delegator(&accounts(3)).is_none());
+        assert!(take_delegator(&accounts(4)).is_none());
+        assert!(take_reviewer_info(&accounts(3)).is_none());
+        assert!(!contract.is_legacy_vapi(VAPI_A.to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Delegator not found or already migrated")]
+    fn shared_entry_moves_only_once() {
+        let mut contract = migrated_contract();
+        contract.migrate_vapi_storage(VAPI_A.to_string(), vec![], vec![accounts(3)]);
+        contract.migrate_vapi_storage(VAPI_B.to_string(), vec![], vec![accounts(3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Reviewer not found or already migrated")]
+    fn shared_reviewer_moves_only_once() {
+        let mut contract = migrated_contract();
+        contract.migrate_vapi_storage(VAPI_A.to_string(), vec![accounts(3)], vec![]);
+        contract.migrate_vapi_storage(VAPI_B.to_string(), vec![accounts(3)], vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "VAPI is not pending migration")]
+    fn vapi_migrates_once() {
+        let mut contract = migrated_contract();
+        contract.migrate_vapi_storage(VAPI_A.to_string(), vec![], vec![accounts(3)]);
+        contract.migrate_vapi_storage(VAPI_A.to_string(), vec![], vec![accounts(4)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Delegator deposits exceed the deposits of the VAPI")]
+    fn attributed_deposits_are_capped() {
+        let mut contract = migrated_contract();
+        contract.migrate_vapi_storage(VAPI_B.to_string(), vec![], vec![accounts(3), accounts(4)]);
+    }
+}
@@ -1,13 +1,61 @@
 use near_contract_standards::fungible_token::core::ext_ft_core;
-use near_contract_standards::fungible_token::resolver::ext_ft_resolver;
 use near_contract_standards::fungible_token::Balance;
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, ext_contract, log, near, require, serde_json, AccountId, Gas, NearToken, PanicOnDefault, Promise, PromiseOrValue, PromiseResult};
-use near_sdk::collections::{LookupMap, UnorderedMap};
-use near_sdk::json_types::U128;
-use ed25519_dalek::{PublicKey, Signature, Verifier};
+use near_sdk::{env, ext_contract, log, near, require, serde_json, AccountId, BorshStorageKey, CryptoHash, CurveType, Gas, NearToken, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, PublicKey};
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
+use std::collections::BTreeMap;
 
+pub mod event;
+pub mod fee;
 pub mod ft_receiver;
+pub mod gateway;
+pub mod legacy;
+pub mod merkle;
+pub mod payload;
+pub mod review;
+pub mod reward;
+pub mod settlement;
+pub mod signer;
+pub mod storage;
+pub mod treasury;
+pub mod unbond;
+
+use event::*;
+use fee::*;
+use gateway::Gateway;
+use payload::ReviewRequestPayload;
+use review::*;
+use reward::*;
+use settlement::{SettlementBatch, SettlementEpoch, SettlementRecord, SETTLEMENT_PAGE_SIZE};
+use signer::SignerKey;
+use storage::*;
+use unbond::*;
+
+#[derive(BorshStorageKey)]
+#[near]
+enum StorageKey {
+    ReviewerInfos { vapi_hash: CryptoHash },
+    DelegatorInfos { vapi_hash: CryptoHash },
+    LegacyVapis,
+    VapisPerCoder,
+    CoderVapis { account_hash: CryptoHash },
+    StorageAccounts,
+    UsedNonces,
+    SignerKeys,
+    Unbonds { vapi_hash: CryptoHash },
+    SettlementBatches,
+    Settlements { vapi_hash: CryptoHash },
+    SettlementBatchEntries { batch_hash: CryptoHash },
+    SettledUntil,
+    SettlementEpochs,
+    SettlementEpochClaims { epoch_hash: CryptoHash },
+    Gateways,
+    UsageReportedUntil,
+    UsagePools,
+    UnbondVapisPerAccount,
+    AccountUnbondVapis { account_hash: CryptoHash },
+}
 
 #[near(contract_state)]
 #[derive(PanicOnDefault)]
@@ -15,7 +63,40 @@ pub struct TicleCore {
     vapis: LookupMap<String, VAPI>,
     token_id: AccountId,
     owner_id: AccountId,
-    signer_public_key: Vec<u8>,
+    signer_keys: UnorderedMap<PublicKey, SignerKey>,
+    signer_threshold: u8,
+    // VAPI별 threshold -> 그 threshold를 쓰는 VAPI 수. 키를 폐기할 때 가장 큰 값과 비교한다.
+    vapi_signer_thresholds: BTreeMap<u8, u64>,
+    zero_stake_policy: ZeroStakePolicy,
+    fee_config: FeeConfig,
+    treasury_id: Option<AccountId>,
+    treasury_balance: Balance,
+    treasury_total_collected: Balance,
+    // token의 burn이 실패해서 (예: 토큰이 일시 정지됨) 아직 태우지 못한 금액. retry_burn으로 다시 보낸다.
+    pending_burn_amount: Balance,
+    // 공용 prefix("r", "d")를 쓰던 시절에 생성되어 아직 마이그레이션되지 않은 VAPI
+    legacy_vapis: LookupSet<String>,
+    vapis_per_coder: LookupMap<AccountId, UnorderedSet<String>>,
+    storage_accounts: LookupMap<AccountId, AccountStorage>,
+    // 리뷰 요청 서명의 재사용을 막기 위해 (코더, nonce) 쌍을 기록한다.
+    used_nonces: LookupSet<(AccountId, u64)>,
+    // 등록된 정산 batch. 같은 batch_id로 두 번 정산되는 것을 막는다.
+    settlement_batches: LookupMap<String, SettlementBatch>,
+    // VAPI별로 정산이 등록된 마지막 기간의 끝 (ns)
+    settled_until: LookupMap<String, u64>,
+    // Merkle root로 올라온 정산 epoch
+    settlement_epochs: LookupMap<String, SettlementEpoch>,
+    // 마지막 epoch의 기간 끝 (ns)
+    epoch_settled_until: u64,
+    // 등록된 정산 batch 중 가장 늦은 기간 끝 (ns)
+    batch_settled_until: u64,
+    gateways: UnorderedMap<AccountId, Gateway>,
+    // (gateway, VAPI)별로 사용량이 보고된 마지막 기간의 끝 (ns)
+    usage_reported_until: LookupMap<(AccountId, String), u64>,
+    // (gateway, VAPI)별로 사용량 정산에 쓸 수 있는 잔액
+    usage_pools: LookupMap<(AccountId, String), Balance>,
+    // 계정별로 unbond가 남아 있는 VAPI. VAPI를 모르는 계정도 unbond를 찾을 수 있게 한다.
+    unbond_vapis_per_account: LookupMap<AccountId, UnorderedSet<String>>,
 }
 
 #[near(serializers = [borsh])]
@@ -23,6 +104,46 @@ pub struct VAPI {
     coder_info: CoderInfo,
     reviewer_infos: UnorderedMap<AccountId, ReviewerInfo>,
 	delegation_info: DelegationInfo,
+    // 리뷰 요청에 필요한 서명 수. None이면 컨트랙트 전체 기본값을 따른다.
+    signer_threshold: Option<u8>,
+    // 코더가 기본값보다 높게 설정한 delegator 몫(bps)
+    delegator_bps: Option<u16>,
+    // 이 VAPI의 정산에서 treasury로 들어간 누적 금액
+    treasury_amount: Balance,
+    settlements: Vector<SettlementRecord>,
+    // gateway 사용량 보고에서 호출 1회당 가격. 0이면 사용량으로 정산하지 않는다.
+    usage_price: Balance,
+}
+
+impl VAPI {
+    /// Creates an empty VAPI whose reviewer and delegator collections live under
+    /// storage prefixes derived from `vapi_id`, so no two VAPIs share trie keys.
+    pub fn new(vapi_id: &str, coder_id: AccountId) -> Self {
+        let vapi_hash = env::sha256_array(vapi_id.as_bytes());
+        Self {
+            coder_info: CoderInfo {
+                account_id: coder_id,
+                unclaimed_reward_amount: 0,
+            },
+            reviewer_infos: UnorderedMap::new(StorageKey::ReviewerInfos { vapi_hash }),
+            delegation_info: DelegationInfo {
+                delegator_infos: LookupMap::new(StorageKey::DelegatorInfos { vapi_hash }),
+                total_deposit_amount: 0,
+                acc_reward_per_share: 0,
+                unbonds: LookupMap::new(StorageKey::Unbonds { vapi_hash }),
+                unbonding_period: DEFAULT_UNBONDING_PERIOD,
+                unreleased_reward_amount: 0,
+                reward_release_period: DEFAULT_REWARD_RELEASE_PERIOD,
+                reward_release_end: 0,
+                last_reward_release_at: 0,
+            },
+            signer_threshold: None,
+            delegator_bps: None,
+            treasury_amount: 0,
+            settlements: Vector::new(StorageKey::Settlements { vapi_hash }),
+            usage_price: 0,
+        }
+    }
 }
 
 #[near(serializers = [borsh])]
@@ -36,6 +157,14 @@ pub struct DelegationInfo {
 	delegator_infos: LookupMap<AccountId, Delegator>,
 	total_deposit_amount: Balance,
 	acc_reward_per_share: Balance,
+	// withdraw 후 unbonding_period(ns)가 지나야 complete_unbond로 수령할 수 있다.
+	unbonds: LookupMap<AccountId, Vec<Unbond>>,
+	unbonding_period: u64,
+	// 정산된 delegator 몫은 reward_release_period(ns) 동안 선형으로 acc_reward_per_share에 반영된다.
+	unreleased_reward_amount: Balance,
+	reward_release_period: u64,
+	reward_release_end: u64,
+	last_reward_release_at: u64,
 }
 
 #[near(serializers = [borsh, json])]
@@ -43,6 +172,8 @@ pub struct ReviewerInfo {
     version: String,
 	royalty_amount: Balance,
     timestamp: u64,
+    status: ReviewStatus,
+    updated_at: u64,
 }
 
 #[near(serializers = [borsh])]
@@ -51,6 +182,18 @@ pub struct Delegator {
     reward_debt: Balance,
 }
 
+#[near(serializers = [json])]
+pub struct VapiEarnings {
+    vapi_id: String,
+    unclaimed_reward_amount: U128,
+}
+
+#[near(serializers = [json])]
+pub struct CoderEarnings {
+    total_unclaimed_amount: U128,
+    vapis: Vec<VapiEarnings>,
+}
+
 #[ext_contract(ext_ft_burn)]
 pub trait FungibleTokenBurn {
     fn burn(&mut self, amount: U128);
@@ -60,107 +203,242 @@ pub trait FungibleTokenBurn {
 impl TicleCore {
     #[init]
     pub fn new(token_id: AccountId, owner_id: AccountId) -> Self {
-        let mut signer_public_key = env::signer_account_pk().into_bytes();
-
-        // HACK: prefix로 맨 앞에 0이 붙어서 33바이트가 되는 경우가 있음
-        if signer_public_key.len() > 32 {
-            signer_public_key.remove(0);
-        }
-        Self {
+        let mut this = Self {
             vapis: LookupMap::new(b"v".to_vec()),
             token_id,
             owner_id,
-            signer_public_key,
+            signer_keys: UnorderedMap::new(StorageKey::SignerKeys),
+            signer_threshold: 1,
+            vapi_signer_thresholds: BTreeMap::new(),
+            zero_stake_policy: ZeroStakePolicy::Undistributed,
+            fee_config: FeeConfig::default(),
+            treasury_id: None,
+            treasury_balance: 0,
+            treasury_total_collected: 0,
+            pending_burn_amount: 0,
+            legacy_vapis: LookupSet::new(StorageKey::LegacyVapis),
+            vapis_per_coder: LookupMap::new(StorageKey::VapisPerCoder),
+            storage_accounts: LookupMap::new(StorageKey::StorageAccounts),
+            used_nonces: LookupSet::new(StorageKey::UsedNonces),
+            settlement_batches: LookupMap::new(StorageKey::SettlementBatches),
+            settled_until: LookupMap::new(StorageKey::SettledUntil),
+            settlement_epochs: LookupMap::new(StorageKey::SettlementEpochs),
+            epoch_settled_until: 0,
+            batch_settled_until: 0,
+            gateways: UnorderedMap::new(StorageKey::Gateways),
+            usage_reported_until: LookupMap::new(StorageKey::UsageReportedUntil),
+            usage_pools: LookupMap::new(StorageKey::UsagePools),
+            unbond_vapis_per_account: LookupMap::new(StorageKey::UnbondVapisPerAccount),
+        };
+
+        // 배포한 계정의 키를 첫 서명 키로 등록한다. 이후에는 owner가 키를 추가·교체한다.
+        this.internal_add_signer_key(env::signer_account_pk(), None, None);
+        return this;
+    }
+
+    /// Upgrades state written by the version that stored every VAPI's reviewers
+    /// under `b"r"` and delegators under `b"d"`. `legacy_vapi_ids` must list every
+    /// VAPI created before the upgrade; each of them stays locked until the owner
+    /// re-homes it with `migrate_vapi_storage`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(legacy_vapi_ids: Vec<String>) -> Self {
+        let old_state: legacy::LegacyTicleCore = env::state_read().expect("Failed to read legacy state");
+
+        let mut legacy_vapis = LookupSet::new(StorageKey::LegacyVapis);
+        for vapi_id in legacy_vapi_ids.iter() {
+            require!(old_state.vapis.contains_key(vapi_id), "VAPI not found");
+            legacy_vapis.insert(vapi_id);
+        }
+
+        let mut this = Self {
+            vapis: LookupMap::new(b"v".to_vec()),
+            token_id: old_state.token_id,
+            owner_id: old_state.owner_id,
+            signer_keys: UnorderedMap::new(StorageKey::SignerKeys),
+            signer_threshold: 1,
+            vapi_signer_thresholds: BTreeMap::new(),
+            zero_stake_policy: ZeroStakePolicy::Undistributed,
+            fee_config: FeeConfig::default(),
+            treasury_id: None,
+            treasury_balance: 0,
+            treasury_total_collected: 0,
+            pending_burn_amount: 0,
+            legacy_vapis,
+            vapis_per_coder: LookupMap::new(StorageKey::VapisPerCoder),
+            storage_accounts: LookupMap::new(StorageKey::StorageAccounts),
+            used_nonces: LookupSet::new(StorageKey::UsedNonces),
+            settlement_batches: LookupMap::new(StorageKey::SettlementBatches),
+            settled_until: LookupMap::new(StorageKey::SettledUntil),
+            settlement_epochs: LookupMap::new(StorageKey::SettlementEpochs),
+            epoch_settled_until: 0,
+            batch_settled_until: 0,
+            gateways: UnorderedMap::new(StorageKey::Gateways),
+            usage_reported_until: LookupMap::new(StorageKey::UsageReportedUntil),
+            usage_pools: LookupMap::new(StorageKey::UsagePools),
+            unbond_vapis_per_account: LookupMap::new(StorageKey::UnbondVapisPerAccount),
+        };
+
+        let signer_public_key = PublicKey::from_parts(CurveType::ED25519, old_state.signer_public_key).expect("Invalid legacy signer key");
+        this.internal_add_signer_key(signer_public_key, None, None);
+        return this;
+    }
+
+    /// Moves a legacy VAPI into its own storage namespace. The shared legacy
+    /// collections do not record which VAPI an entry belongs to, so the owner
+    /// attributes the reviewers and delegators of `vapi_id` (recovered off-chain from
+    /// the transaction history). Each legacy entry is removed once it is moved, so it
+    /// can be attributed to one VAPI only, and the attributed deposits can't exceed the
+    /// deposits the VAPI recorded.
+    pub fn migrate_vapi_storage(&mut self, vapi_id: String, reviewer_ids: Vec<AccountId>, delegator_ids: Vec<AccountId>) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can migrate");
+        require!(self.legacy_vapis.remove(&vapi_id), "VAPI is not pending migration");
+
+        let legacy_vapi = legacy::take_vapi(&vapi_id).expect("VAPI not found");
+        let mut vapi = VAPI::new(&vapi_id, legacy_vapi.coder_info.account_id);
+        vapi.coder_info.unclaimed_reward_amount = legacy_vapi.coder_info.unclaimed_reward_amount;
+        vapi.delegation_info.acc_reward_per_share = legacy_vapi.delegation_info.acc_reward_per_share;
+
+        for reviewer_id in reviewer_ids.iter() {
+            let reviewer_info = legacy::take_reviewer_info(reviewer_id).expect("Reviewer not found or already migrated");
+            vapi.reviewer_infos.insert(reviewer_id, &reviewer_info);
+        }
+
+        let mut total_deposit_amount: Balance = 0;
+        for delegator_id in delegator_ids.iter() {
+            let delegator_info = legacy::take_delegator(delegator_id).expect("Delegator not found or already migrated");
+            total_deposit_amount += delegator_info.deposit_amount;
+            vapi.delegation_info.delegator_infos.insert(delegator_id, &delegator_info);
         }
+        require!(
+            total_deposit_amount <= legacy_vapi.delegation_info.total_deposit_amount,
+            "Delegator deposits exceed the deposits of the VAPI"
+        );
+        // 다른 VAPI와 겹쳐 덮어써진 예치금은 주인을 알 수 없으므로 보상 분배에서 뺀다.
+        vapi.delegation_info.total_deposit_amount = total_deposit_amount;
+
+        self.vapis.insert(&vapi_id, &vapi);
+        self.internal_add_coder_vapi(&vapi.coder_info.account_id, &vapi_id);
+        log!(
+            "[migrate_vapi_storage] {}: {} reviewers, {} delegators, {} of {} deposits attributed",
+            vapi_id, reviewer_ids.len(), delegator_ids.len(), total_deposit_amount, legacy_vapi.delegation_info.total_deposit_amount
+        );
+    }
+
+    pub fn is_legacy_vapi(&self, vapi_id: String) -> bool {
+        return self.legacy_vapis.contains(&vapi_id);
+    }
+
+    pub fn is_nonce_used(&self, coder_id: AccountId, nonce: U64) -> bool {
+        return self.used_nonces.contains(&(coder_id, nonce.0));
     }
 }
 
 #[near]
 impl TicleCore {
     pub fn create_vapi(&mut self, vapi_id: String) {
+        require!(!self.vapis.contains_key(&vapi_id), "VAPI already exists");
+
+        let initial_storage_usage = env::storage_usage();
         let coder_id = env::predecessor_account_id();
-        let vapi = VAPI {
-            coder_info: CoderInfo {
-                account_id: coder_id,
-                unclaimed_reward_amount: 0,
-            },
-            reviewer_infos: UnorderedMap::new(b"r".to_vec()),
-            delegation_info: DelegationInfo {
-                delegator_infos: LookupMap::new(b"d".to_vec()),
-                total_deposit_amount: 0,
-                acc_reward_per_share: 0,
-            },
-        };
+        self.assert_storage_available(&coder_id, VAPI_STORAGE_BYTES);
+        let vapi = VAPI::new(&vapi_id, coder_id.clone());
         self.vapis.insert(&vapi_id, &vapi);
+        self.internal_add_coder_vapi(&coder_id, &vapi_id);
+        self.internal_update_storage(&coder_id, initial_storage_usage);
+
+        EventLogVariant::VapiCreate(vec![VapiCreateLog {
+            vapi_id,
+            coder_id,
+        }]).emit();
     }
 
-    pub fn cancel_review(&mut self, vapi_id: String, reviewer_ids: Vec<AccountId>) -> Promise {
-        require!(reviewer_ids.len() > 0, "At least one reviewer must be provided");
-        require!(reviewer_ids.len() <= 3, "Maximum 3 reviewers can be cancelled");
-        let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
+    // TODO: 삭제해야 하는지 확인해보기
+    pub fn transfer_ownership(&mut self, vapi_id: String, new_coder_id: AccountId) {
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+
+        let account_id = env::predecessor_account_id();
+        require!(vapi.coder_info.account_id == account_id, "Only coder can transfer ownership");
 
+        let initial_storage_usage = env::storage_usage();
+        vapi.coder_info.account_id = new_coder_id.clone();
+        self.vapis.insert(&vapi_id, &vapi);
+        self.internal_remove_coder_vapi(&account_id, &vapi_id);
+        self.internal_add_coder_vapi(&new_coder_id, &vapi_id);
+        self.internal_update_storage(&account_id, initial_storage_usage);
+
+        EventLogVariant::OwnershipTransfer(vec![OwnershipTransferLog {
+            vapi_id,
+            old_coder_id: account_id,
+            new_coder_id,
+        }]).emit();
+    }
+
+    /// Pays out the coder's share of settlements. Claims everything that is unclaimed
+    /// when `amount` is omitted.
+    pub fn claim_coder_earnings(&mut self, vapi_id: String, amount: Option<U128>) -> Promise {
         let coder_id = env::predecessor_account_id();
-        require!(vapi.coder_info.account_id == coder_id, "Only coder can cancel review");
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        require!(vapi.coder_info.account_id == coder_id, "Only coder can claim earnings");
 
-        let mut combined_promise: Option<Promise> = None;
-        let contract_id = env::current_account_id();
-        for reviewer_id in reviewer_ids {
-            let reviewer_info = vapi.reviewer_infos.get(&reviewer_id).expect("Reviewer not found");
-            let royalty_amount = U128(reviewer_info.royalty_amount);
-            
-            let promise = ext_ft_core::ext(self.token_id.clone())
-                .with_attached_deposit(NearToken::from_yoctonear(1))
-                .with_static_gas(Gas::from_tgas(20))
-                .ft_transfer(reviewer_id.clone(), royalty_amount, None)
-                .then(
-                    ext_ft_resolver::ext(contract_id.clone())
-                        .with_static_gas(Gas::from_tgas(10))
-                        .ft_resolve_transfer(contract_id.clone(), reviewer_id.clone(), royalty_amount),
-                );
-            
-            if let Some(existing_promise) = combined_promise {
-                combined_promise = Some(existing_promise.and(promise));
-            } else {
-                combined_promise = Some(promise);
-            }
-            
-            vapi.reviewer_infos.remove(&reviewer_id);
-        }
+        let unclaimed_reward_amount = vapi.coder_info.unclaimed_reward_amount;
+        let amount: Balance = amount.map(|amount| amount.0).unwrap_or(unclaimed_reward_amount);
+        require!(amount > 0, "No earnings to claim");
+        require!(amount <= unclaimed_reward_amount, "Not enough unclaimed earnings");
 
+        vapi.coder_info.unclaimed_reward_amount -= amount;
         self.vapis.insert(&vapi_id, &vapi);
-        return combined_promise
-            .unwrap()
+
+        return ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(20))
+            .ft_transfer(coder_id.clone(), U128(amount), None)
             .then(
-                Self::ext(contract_id.clone())
+                Self::ext(env::current_account_id())
                     .with_static_gas(Gas::from_tgas(20))
-                    .callback_cancel_review()
+                    .callback_claim_coder_earnings(coder_id, vapi_id, U128(amount))
             );
     }
 
     #[private]
-    pub fn callback_cancel_review(&self) {
-        let promise_count = env::promise_results_count();
-        assert!(promise_count > 0, "Expected at least one promise result.");
-
-        for i in 0..promise_count {
-            match env::promise_result(i) {
-                PromiseResult::Successful(_) => {
-                    // do nothing
-                }
-                _ => env::panic_str("ft_transfer failed during cancel review"),
+    pub fn callback_claim_coder_earnings(&mut self, coder_id: AccountId, vapi_id: String, amount: U128) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                EventLogVariant::CoderEarningsClaim(vec![CoderEarningsClaimLog {
+                    vapi_id,
+                    coder_id,
+                    amount,
+                }]).emit();
+            }
+            _ => {
+                // ft_transfer가 실패하면 차감했던 금액을 되돌린다.
+                log!("[callback_claim_coder_earnings] ft_transfer failed, restoring {}", amount.0);
+                let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
+                vapi.coder_info.unclaimed_reward_amount += amount.0;
+                self.vapis.insert(&vapi_id, &vapi);
             }
         }
     }
 
-    // TODO: 삭제해야 하는지 확인해보기
-    pub fn transfer_ownership(&mut self, vapi_id: String, new_coder_id: AccountId) {
-        let mut vapi = self.vapis.get(&vapi_id).expect("Vertical API not found");
-
-        let account_id = env::predecessor_account_id();
-        require!(vapi.coder_info.account_id == account_id, "Only coder can transfer ownership");
+    /// Unclaimed coder earnings of `account_id` across every VAPI they own.
+    pub fn coder_earnings(&self, account_id: AccountId) -> CoderEarnings {
+        let mut total_unclaimed_amount: Balance = 0;
+        let mut vapis: Vec<VapiEarnings> = Vec::new();
+        if let Some(vapi_ids) = self.vapis_per_coder.get(&account_id) {
+            for vapi_id in vapi_ids.iter() {
+                let vapi = self.internal_get_vapi(&vapi_id);
+                total_unclaimed_amount += vapi.coder_info.unclaimed_reward_amount;
+                vapis.push(VapiEarnings {
+                    vapi_id,
+                    unclaimed_reward_amount: U128(vapi.coder_info.unclaimed_reward_amount),
+                });
+            }
+        }
 
-        vapi.coder_info.account_id = new_coder_id;
-        self.vapis.insert(&vapi_id, &vapi);
+        return CoderEarnings {
+            total_unclaimed_amount: U128(total_unclaimed_amount),
+            vapis,
+        };
     }
 
     pub fn claim_reward(&mut self, vapi_id: String) -> Promise {
@@ -168,80 +446,88 @@ impl TicleCore {
         return self.internal_claim_reward(&sender_id, vapi_id.clone());
     }
 
+    /// Claims pending rewards and moves `amount` of the stake into an unbond entry.
+    /// The principal is paid out by `complete_unbond` after the VAPI's unbonding period;
+    /// rewards released before the unbond are paid out right away.
     pub fn withdraw(&self, vapi_id: String, amount: U128) -> Promise {
         let sender_id = env::predecessor_account_id();
+        let vapi = self.internal_get_vapi(&vapi_id);
+        let delegator_info = vapi.delegation_info.delegator_infos.get(&sender_id).expect("Delegator not found");
+        require!(amount.0 > 0, "Invalid amount");
+        require!(amount.0 <= delegator_info.deposit_amount, "Not enough deposit");
+        self.assert_storage_available(&sender_id, UNBOND_STORAGE_BYTES);
+
         return self.internal_claim_reward(&sender_id, vapi_id.clone()).then(
             Self::ext(env::current_account_id())
-                .with_static_gas(Gas::from_tgas(20))
+                .with_static_gas(Gas::from_tgas(60))
                 .callback_withdraw(&sender_id, vapi_id.clone(), amount)
         );
     }
 
-    pub fn claim_review_reward(&self, vapi_id: String) -> Promise {
-        let sender_id = env::predecessor_account_id();
-        let vapi = self.vapis.get(&vapi_id).expect("Vertical API not found");
-        let reviewer_info = vapi.reviewer_infos.get(&sender_id).expect("Reviewer not found");
-        
-        let timestamp = reviewer_info.timestamp;
-        let current_timestamp = env::block_timestamp();
-
-        const TWO_WEEKS: u64 = 60 * 60 * 24 * 14 * 1000000;
-        log!("[claim_review_reward] current_timestamp: {}, timestamp: {}", current_timestamp, timestamp);
-        require!(current_timestamp >= timestamp + TWO_WEEKS, "Reviewer can claim reward after 2 weeks");
-
-        return ext_ft_core::ext(self.token_id.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(20))
-            .ft_transfer(sender_id.clone(), U128(reviewer_info.royalty_amount), None)
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(Gas::from_tgas(20))
-                    .callback_claim_review_reward(sender_id.clone(), vapi_id.clone())
-            );
-    }
-
     #[private]
-    pub fn callback_pending_reward(&mut self, sender_id: &AccountId, vapi_id: String) {
+    pub fn callback_pending_reward(&mut self, sender_id: &AccountId, vapi_id: String, amount: U128) {
         let mut vapi = self.vapis.get(&vapi_id).unwrap();
         let mut delegator_info = vapi.delegation_info.delegator_infos.get(sender_id).unwrap();
         
-        delegator_info.reward_debt = delegator_info.deposit_amount * vapi.delegation_info.acc_reward_per_share / 1_000_000_000_000;
+        // 보상이 선형으로 풀리는 중이면 acc_reward_per_share가 아직 저장되지 않았을 수 있으므로 지급한 금액만큼만 차감한다.
+        delegator_info.reward_debt += amount.0;
         vapi.delegation_info.delegator_infos.insert(sender_id, &delegator_info);
 
         self.vapis.insert(&vapi_id, &vapi);
+
+        EventLogVariant::RewardClaim(vec![RewardClaimLog {
+            vapi_id,
+            account_id: sender_id.clone(),
+            amount,
+        }]).emit();
     }
 
     #[private]
-    pub fn callback_withdraw(&mut self, sender_id: &AccountId, vapi_id: String, amount: U128) -> Promise {
-        let mut vapi = self.vapis.get(&vapi_id).expect("Vertical API not found");
-        let mut delegator_info = vapi.delegation_info.delegator_infos.get(sender_id).expect("Delegator not found");
-        delegator_info.deposit_amount -= amount.0;
-
-        if delegator_info.deposit_amount == 0 {
-            vapi.delegation_info.delegator_infos.remove(sender_id);
-        }
-        vapi.delegation_info.total_deposit_amount -= amount.0;
+    pub fn callback_withdraw(&mut self, sender_id: &AccountId, vapi_id: String, amount: U128) -> PromiseOrValue<()> {
+        let initial_storage_usage = env::storage_usage();
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        let (unlock_at, reward_amount) = self.internal_unbond(&mut vapi, &vapi_id, sender_id, amount.0);
         self.vapis.insert(&vapi_id, &vapi);
+        self.internal_update_storage(sender_id, initial_storage_usage);
+        log!("[callback_withdraw] {} unlocks at {}", amount.0, unlock_at);
 
-        return ext_ft_core::ext(self.token_id.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(20))
-            .ft_transfer(sender_id.clone(), amount, None);
+        EventLogVariant::Withdraw(vec![WithdrawLog {
+            vapi_id: vapi_id.clone(),
+            account_id: sender_id.clone(),
+            amount,
+        }]).emit();
+
+        if reward_amount == 0 {
+            return PromiseOrValue::Value(());
+        }
+        // withdraw에서 보상을 수령한 뒤 callback까지 풀린 보상은 원금과 달리 잠그지 않고 바로 지급한다.
+        return PromiseOrValue::Promise(
+            ext_ft_core::ext(self.token_id.clone())
+                .with_attached_deposit(NearToken::from_yoctonear(1))
+                .with_static_gas(Gas::from_tgas(20))
+                .ft_transfer(sender_id.clone(), U128(reward_amount), None)
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(Gas::from_tgas(20))
+                        .callback_withdraw_reward(sender_id.clone(), vapi_id, U128(reward_amount))
+                )
+        );
     }
 
     pub fn pending_reward(&self, sender_id: &AccountId, vapi_id: String) -> Balance {
         log!("[pending_reward] {}", sender_id);
-        let vapi = self.vapis.get(&vapi_id).expect("Vertical API not found");
+        let mut delegation_info = self.internal_get_vapi(&vapi_id).delegation_info;
         log!("[pending_reward] found vertical_api");
-        let delegator_info = vapi.delegation_info.delegator_infos.get(&sender_id).unwrap_or(Delegator {
+        internal_release_rewards(&mut delegation_info);
+        let delegator_info = delegation_info.delegator_infos.get(sender_id).unwrap_or(Delegator {
             deposit_amount: 0,
             reward_debt: 0,
         });
 
         log!("[pending_reward] deposit_amount: {}, reward_debt: {}", delegator_info.deposit_amount, delegator_info.reward_debt);
-        log!("[pending_reward] acc_reward_per_share: {}", vapi.delegation_info.acc_reward_per_share);
+        log!("[pending_reward] acc_reward_per_share: {}", delegation_info.acc_reward_per_share);
 
-        return (delegator_info.deposit_amount * vapi.delegation_info.acc_reward_per_share / 1_000_000_000_000) - delegator_info.reward_debt;
+        return internal_pending_reward(&delegator_info, delegation_info.acc_reward_per_share);
     }
 }
 
@@ -249,7 +535,11 @@ impl TicleCore {
 impl TicleCore {
     fn internal_deposit(&mut self, sender_id: &AccountId, vapi_id: String, amount: Balance) -> Promise {
         log!("[internal_deposit] deposit to vapi: {}", vapi_id);
-        
+        let vapi = self.internal_get_vapi(&vapi_id);
+        if !vapi.delegation_info.delegator_infos.contains_key(sender_id) {
+            self.assert_storage_available(sender_id, DELEGATOR_STORAGE_BYTES);
+        }
+
         let contract_id = env::current_account_id();
         return self.internal_claim_reward(sender_id, vapi_id.clone()).then(
             Self::ext(contract_id.clone())
@@ -260,91 +550,62 @@ impl TicleCore {
 
     #[private]
     pub fn callback_internal_deposit(&mut self, sender_id: AccountId, vapi_id: String, amount: Balance) {
-        let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
+        let initial_storage_usage = env::storage_usage();
+        let mut vapi = self.internal_get_vapi(&vapi_id);
         log!("[callback_internal_deposit] found vertical_api");
         
         let mut delegation_info = vapi.delegation_info;
-        delegation_info.total_deposit_amount += amount;
-        
-        let reward_amount = self.pending_reward(&sender_id, vapi_id.clone());
-        if reward_amount > 0 {
-            delegation_info.acc_reward_per_share += reward_amount * 1_000_000_000_000 / delegation_info.total_deposit_amount;
-        }
-        
+        internal_release_rewards(&mut delegation_info);
+
         let mut delegator_info = delegation_info.delegator_infos.get(&sender_id).unwrap_or(Delegator {
             deposit_amount: 0,
             reward_debt: 0,
         });
+        // 입금 전까지 쌓인 보상은 기존 예치금에 대한 것이므로 그대로 남겨둔다.
+        let reward_amount = internal_pending_reward(&delegator_info, delegation_info.acc_reward_per_share);
         delegator_info.deposit_amount += amount;
-        delegator_info.reward_debt = delegator_info.deposit_amount * delegation_info.acc_reward_per_share / 1_000_000_000_000;
+        delegator_info.reward_debt = delegator_info.deposit_amount * delegation_info.acc_reward_per_share / ACC_REWARD_PRECISION - reward_amount;
+        delegation_info.total_deposit_amount += amount;
         
         delegation_info.delegator_infos.insert(&sender_id, &delegator_info);
         vapi.delegation_info = delegation_info;
         self.vapis.insert(&vapi_id, &vapi);
+        self.internal_update_storage(&sender_id, initial_storage_usage);
 
         log!("[callback_internal_deposit] success: {}", vapi.delegation_info.total_deposit_amount);
-    }
 
-    #[private]
-    pub fn callback_claim_review_reward(&mut self, sender_id: AccountId, vapi_id: String) {
-        let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
-        vapi.reviewer_infos.remove(&sender_id);
-        self.vapis.insert(&vapi_id, &vapi);
+        EventLogVariant::Deposit(vec![DepositLog {
+            vapi_id,
+            account_id: sender_id,
+            amount: U128(amount),
+        }]).emit();
     }
 
-    fn internal_settlement (&mut self, sender_id: &AccountId, vapi_ids: Vec<String>, amounts: Vec<U128>) -> Promise {
-        log!("[internal_settlement]");
-        require!(*sender_id == self.owner_id, "Only owner can settle");
-        require!(vapi_ids.len() == amounts.len(), "vapi_ids and amounts must have the same length");
-
-        let mut total_burn_amount: u128 = 0;
-        for (vapi_id, amount) in vapi_ids.iter().zip(amounts.iter()) {
-            let amount: Balance = amount.0;
-            let delegator_fee_amount = amount * 39 / 100;
-            let burn_amount = amount * 1 / 100;
-
-            let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
-            vapi.coder_info.unclaimed_reward_amount += amount - delegator_fee_amount - burn_amount;
-            vapi.delegation_info.acc_reward_per_share += delegator_fee_amount * 1_000_000_000_000 / vapi.delegation_info.total_deposit_amount;
-            self.vapis.insert(&vapi_id, &vapi);
-
-            total_burn_amount += burn_amount;
-        }
-
-        return ext_ft_burn::ext(self.token_id.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(20))
-            .burn(U128(total_burn_amount));
-    }
-
-    fn internal_request_review(
-        &mut self,
-        vapi_id: String,
-        version: String,
-        reviewer_ids: Vec<AccountId>,
-        royalty_amounts: Vec<U128>,
-        transfer_sender_id: AccountId,
-        transfer_amount: U128,
-        signature: String,
-    ) {
+    fn internal_request_review(&mut self, payload: ReviewRequestPayload, transfer_amount: U128, signatures: Vec<String>) {
         log!("[internal_request_review]");
-        require!(reviewer_ids.len() == royalty_amounts.len(), "reviewer_ids and royalty_amounts must have the same length");
-        let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
-        require!(vapi.coder_info.account_id == transfer_sender_id, "Only coder can request review");
+        let initial_storage_usage = env::storage_usage();
+        require!(payload.reviewer_ids.len() == payload.royalty_amounts.len(), "reviewer_ids and royalty_amounts must have the same length");
+        let mut vapi = self.internal_get_vapi(&payload.vapi_id);
+        require!(vapi.coder_info.account_id == payload.coder_id, "Only coder can request review");
+        self.assert_storage_available(&payload.coder_id, REVIEWER_STORAGE_BYTES * payload.reviewer_ids.len() as u64);
 
-        let message = format!("{},{},{:?},{:?}", vapi_id, version, reviewer_ids, royalty_amounts);
-        let message_bytes = message.as_bytes();
-        require!(self.verify_signature(message_bytes, signature), "Invalid signature");
+        let threshold = vapi.signer_threshold.unwrap_or(self.signer_threshold);
+        self.assert_signatures(&payload.hash(), signatures, threshold);
+        require!(env::block_timestamp() < payload.expires_at, "Signature expired");
+        require!(self.used_nonces.insert(&(payload.coder_id.clone(), payload.nonce)), "Nonce already used");
 
         let timestamp = env::block_timestamp();
         let mut total_royalty_amount: Balance = 0;
-        for (reviewer_id, royalty_amount) in reviewer_ids.iter().zip(royalty_amounts.iter()) {
-            let royalty_amount: Balance = royalty_amount.0;
+        for (reviewer_id, royalty_amount) in payload.reviewer_ids.iter().zip(payload.royalty_amounts.iter()) {
+            require!(vapi.reviewer_infos.get(reviewer_id).is_none(), "Reviewer already has an open review");
+            let royalty_amount: Balance = *royalty_amount;
             total_royalty_amount += royalty_amount;
             vapi.reviewer_infos.insert(&reviewer_id, &ReviewerInfo {
-                version: version.clone(),
+                version: payload.vapi_version.clone(),
                 royalty_amount,
                 timestamp,
+                status: ReviewStatus::Requested,
+                updated_at: timestamp,
             });
         }
 
@@ -353,7 +614,16 @@ impl TicleCore {
 
         require!(total_royalty_amount == transfer_amount.0, "Invalid amount");
 
-        self.vapis.insert(&vapi_id, &vapi);
+        self.vapis.insert(&payload.vapi_id, &vapi);
+        self.internal_update_storage(&payload.coder_id, initial_storage_usage);
+
+        EventLogVariant::ReviewRequest(vec![ReviewRequestLog {
+            vapi_id: payload.vapi_id,
+            coder_id: payload.coder_id,
+            version: payload.vapi_version,
+            reviewer_ids: payload.reviewer_ids,
+            royalty_amounts: payload.royalty_amounts.into_iter().map(U128).collect(),
+        }]).emit();
     }
 
     fn internal_claim_reward(&self, sender_id: &AccountId, vapi_id: String) -> Promise {
@@ -371,48 +641,110 @@ impl TicleCore {
             .ft_transfer(sender_id.clone(), U128(reward_amount), None).then(
                 Self::ext(contract_id.clone())
                     .with_static_gas(Gas::from_tgas(20))
-                    .callback_pending_reward(&sender_id, vapi_id.clone())
+                    .callback_pending_reward(sender_id, vapi_id.clone(), U128(reward_amount))
             )
             .into();
     }
 
-    fn verify_signature(&self, message: &[u8], signature: String) -> bool {
-        let signature_base58 = signature.trim_start_matches("ed25519:");
-
-        // Decode the base58 signature
-        let signature_bytes = match bs58::decode(&signature_base58).into_vec() {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                log!("[verify_signature] Invalid base58 in signature");
-                return false;
-            }
-        };
+    fn internal_add_coder_vapi(&mut self, coder_id: &AccountId, vapi_id: &String) {
+        let mut vapi_ids = self.vapis_per_coder.get(coder_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::CoderVapis { account_hash: env::sha256_array(coder_id.as_str().as_bytes()) })
+        });
+        vapi_ids.insert(vapi_id);
+        self.vapis_per_coder.insert(coder_id, &vapi_ids);
+    }
 
-        // Convert the stored public key bytes to a PublicKey object
-        let public_key = match PublicKey::from_bytes(&self.signer_public_key) {
-            Ok(pk) => pk,
-            Err(_) => {
-                log!("[verify_signature] Invalid public key");
-                return false;
+    fn internal_remove_coder_vapi(&mut self, coder_id: &AccountId, vapi_id: &String) {
+        if let Some(mut vapi_ids) = self.vapis_per_coder.get(coder_id) {
+            vapi_ids.remove(vapi_id);
+            if vapi_ids.is_empty() {
+                self.vapis_per_coder.remove(coder_id);
+            } else {
+                self.vapis_per_coder.insert(coder_id, &vapi_ids);
             }
-        };
+        }
+    }
 
-        // Convert the signature bytes to a Signature object
-        let signature = match Signature::from_bytes(&signature_bytes) {
-            Ok(sig) => sig,
-            Err(_) => {
-                log!("[verify_signature] Invalid signature");
-                return false;
-            }
-        };
+    fn internal_get_vapi(&self, vapi_id: &String) -> VAPI {
+        require!(!self.legacy_vapis.contains(vapi_id), "VAPI storage must be migrated first");
+        return self.vapis.get(vapi_id).expect("VAPI not found");
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const VAPI_A: &str = "vapi-a";
+    const VAPI_B: &str = "vapi-b";
+
+    fn set_context(predecessor_id: AccountId, deposit: NearToken, promise_results: Vec<PromiseResult>) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(5))
+                .predecessor_account_id(predecessor_id)
+                .attached_deposit(deposit)
+                .build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            promise_results
+        );
+    }
 
-        // Verify the signature
-        match public_key.verify(message, &signature) {
-            Ok(_) => true,
-            Err(e) => {
-                log!("[verify_signature] verification error: {:?}", e);
-                false
-            }
+    /// `accounts(1)` owns `vapi-a` with 100 and `vapi-b` with 50 unclaimed earnings.
+    fn contract_with_earnings() -> TicleCore {
+        set_context(accounts(0), NearToken::from_yoctonear(0), vec![]);
+        let mut contract = TicleCore::new("token.near".parse().unwrap(), accounts(0));
+
+        set_context(accounts(1), NearToken::from_near(1), vec![]);
+        contract.storage_deposit(None, None);
+        for (vapi_id, earnings) in [(VAPI_A, 100), (VAPI_B, 50)] {
+            contract.create_vapi(vapi_id.to_string());
+            let mut vapi = contract.internal_get_vapi(&vapi_id.to_string());
+            vapi.coder_info.unclaimed_reward_amount = earnings;
+            contract.vapis.insert(&vapi_id.to_string(), &vapi);
         }
+        set_context(accounts(1), NearToken::from_yoctonear(0), vec![]);
+        return contract;
     }
-}
\ No newline at end of file
+
+    fn unclaimed(contract: &TicleCore, vapi_id: &str) -> Balance {
+        return contract.internal_get_vapi(&vapi_id.to_string()).coder_info.unclaimed_reward_amount;
+    }
+
+    #[test]
+    fn earnings_are_summed_across_vapis_and_claimed_per_vapi() {
+        let mut contract = contract_with_earnings();
+        assert_eq!(contract.coder_earnings(accounts(1)).total_unclaimed_amount, U128(150));
+
+        contract.claim_coder_earnings(VAPI_A.to_string(), Some(U128(40)));
+        let earnings = contract.coder_earnings(accounts(1));
+        assert_eq!(earnings.total_unclaimed_amount, U128(110));
+        assert_eq!(earnings.vapis.len(), 2);
+        assert_eq!(unclaimed(&contract, VAPI_A), 60);
+        assert_eq!(unclaimed(&contract, VAPI_B), 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "No earnings to claim")]
+    fn claim_without_earnings_fails() {
+        let mut contract = contract_with_earnings();
+        contract.claim_coder_earnings(VAPI_B.to_string(), None);
+        contract.claim_coder_earnings(VAPI_B.to_string(), None);
+    }
+
+    #[test]
+    fn failed_transfer_restores_earnings() {
+        let mut contract = contract_with_earnings();
+        contract.claim_coder_earnings(VAPI_A.to_string(), None);
+        assert_eq!(unclaimed(&contract, VAPI_A), 0);
+
+        set_context(accounts(5), NearToken::from_yoctonear(0), vec![PromiseResult::Failed]);
+        contract.callback_claim_coder_earnings(accounts(1), VAPI_A.to_string(), U128(100));
+        assert_eq!(unclaimed(&contract, VAPI_A), 100);
+        assert_eq!(contract.coder_earnings(accounts(1)).total_unclaimed_amount, U128(150));
+    }
+}
@@ -0,0 +1,125 @@
+//! Merkle tree over the per-VAPI revenue of a settlement epoch.
+//!
+//! The off-chain billing service builds the tree with `merkle_root` and hands every VAPI
+//! its `merkle_proof`; `TicleCore` only stores the root and checks proofs with
+//! `verify_merkle_proof`. Pairs are hashed in sorted order, so a proof is just the list
+//! of sibling hashes from the leaf up, without left/right flags.
+
+use near_sdk::borsh;
+use near_sdk::{env, near, CryptoHash};
+
+/// Prefix of leaf hashes. Leaves and inner nodes use different prefixes, so an inner
+/// node can never be passed off as a leaf.
+const LEAF_PREFIX: u8 = 0;
+
+/// Prefix of inner node hashes.
+const NODE_PREFIX: u8 = 1;
+
+/// Revenue of one VAPI in an epoch.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettlementLeaf {
+    pub vapi_id: String,
+    pub amount: u128,
+}
+
+impl SettlementLeaf {
+    /// `sha256(LEAF_PREFIX || borsh(self))`
+    pub fn hash(&self) -> CryptoHash {
+        let mut bytes = vec![LEAF_PREFIX];
+        bytes.extend(borsh::to_vec(self).expect("Failed to serialize settlement leaf"));
+        return env::sha256_array(&bytes);
+    }
+}
+
+fn hash_pair(a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    let mut bytes = vec![NODE_PREFIX];
+    bytes.extend_from_slice(left);
+    bytes.extend_from_slice(right);
+    return env::sha256_array(&bytes);
+}
+
+/// Hashes every level of the tree, leaves first. A node without a sibling moves up
+/// unchanged.
+fn tree_levels(leaves: &[SettlementLeaf]) -> Vec<Vec<CryptoHash>> {
+    let mut levels = vec![leaves.iter().map(SettlementLeaf::hash).collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        let level = levels.last().unwrap();
+        let next = level
+            .chunks(2)
+            .map(|pair| if pair.len() == 2 { hash_pair(&pair[0], &pair[1]) } else { pair[0] })
+            .collect();
+        levels.push(next);
+    }
+    return levels;
+}
+
+pub fn merkle_root(leaves: &[SettlementLeaf]) -> CryptoHash {
+    if leaves.is_empty() {
+        return [0; 32];
+    }
+    return tree_levels(leaves).last().unwrap()[0];
+}
+
+/// Sibling hashes that prove `leaves[index]` is part of `merkle_root(leaves)`.
+pub fn merkle_proof(leaves: &[SettlementLeaf], index: usize) -> Vec<CryptoHash> {
+    let levels = tree_levels(leaves);
+    let mut proof = Vec::new();
+    let mut index = index;
+    for level in levels.iter().take(levels.len() - 1) {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        index /= 2;
+    }
+    return proof;
+}
+
+pub fn verify_merkle_proof(leaf: &SettlementLeaf, proof: &[CryptoHash], root: &CryptoHash) -> bool {
+    let computed = proof.iter().fold(leaf.hash(), |hash, sibling| hash_pair(&hash, sibling));
+    return computed == *root;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<SettlementLeaf> {
+        (0..count)
+            .map(|index| SettlementLeaf { vapi_id: format!("vapi-{}", index), amount: 100 * (index as u128 + 1) })
+            .collect()
+    }
+
+    #[test]
+    fn every_leaf_has_a_valid_proof() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                assert!(verify_merkle_proof(leaf, &merkle_proof(&leaves, index), &root), "{} of {}", index, count);
+            }
+        }
+    }
+
+    #[test]
+    fn proof_does_not_verify_another_amount_or_vapi() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let proof = merkle_proof(&leaves, 2);
+
+        let mut leaf = leaves[2].clone();
+        leaf.amount += 1;
+        assert!(!verify_merkle_proof(&leaf, &proof, &root));
+
+        assert!(!verify_merkle_proof(&leaves[3], &proof, &root));
+    }
+
+    #[test]
+    fn single_leaf_is_its_own_root() {
+        let leaves = leaves(1);
+        assert_eq!(merkle_root(&leaves), leaves[0].hash());
+        assert!(merkle_proof(&leaves, 0).is_empty());
+    }
+}
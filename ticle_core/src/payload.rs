@@ -0,0 +1,131 @@
+//! Bytes that the off-chain review server and the API gateways sign and `TicleCore` verifies.
+//!
+//! Servers and tests should build a `ReviewRequestPayload` and sign `hash()` instead of
+//! re-implementing the encoding, so both sides always agree on the exact bytes.
+
+use near_sdk::{near, AccountId, CryptoHash};
+use ticle_signing::{signing_bytes, signing_hash, TAG_BASE};
+
+/// Domain separation tag of review requests. See `ticle_signing::TAG_BASE`.
+pub const REVIEW_REQUEST_TAG: u32 = TAG_BASE + 1;
+
+/// Bump together with the `ReviewRequestPayload` layout.
+pub const REVIEW_REQUEST_PAYLOAD_VERSION: u8 = 1;
+
+/// Domain separation tag of usage reports signed by API gateways. See `ticle_signing::TAG_BASE`.
+pub const USAGE_REPORT_TAG: u32 = TAG_BASE + 2;
+
+/// Bump together with the `UsageReportPayload` layout.
+pub const USAGE_REPORT_PAYLOAD_VERSION: u8 = 2;
+
+/// Everything a review request signature commits to.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReviewRequestPayload {
+    /// TicleCore account the request is meant for.
+    pub contract_id: AccountId,
+    pub vapi_id: String,
+    pub vapi_version: String,
+    pub coder_id: AccountId,
+    pub reviewer_ids: Vec<AccountId>,
+    pub royalty_amounts: Vec<u128>,
+    pub nonce: u64,
+    /// Block timestamp in nanoseconds after which the signature is rejected.
+    pub expires_at: u64,
+}
+
+impl ReviewRequestPayload {
+    /// `borsh(REVIEW_REQUEST_TAG) || REVIEW_REQUEST_PAYLOAD_VERSION || borsh(self)`
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        return signing_bytes(REVIEW_REQUEST_TAG, REVIEW_REQUEST_PAYLOAD_VERSION, self);
+    }
+
+    /// SHA-256 of `signing_bytes()`. This is the message that gets signed.
+    pub fn hash(&self) -> CryptoHash {
+        return signing_hash(REVIEW_REQUEST_TAG, REVIEW_REQUEST_PAYLOAD_VERSION, self);
+    }
+}
+
+/// Calls one gateway served for one VAPI during a period.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsageReportPayload {
+    /// TicleCore account the report is meant for.
+    pub contract_id: AccountId,
+    pub gateway_id: AccountId,
+    pub vapi_id: String,
+    /// Usage period, as block timestamps in nanoseconds.
+    pub period_start: u64,
+    pub period_end: u64,
+    pub calls: u64,
+    /// Price of one call the gateway agreed to. Reports are rejected when the VAPI's
+    /// usage price changed after signing.
+    pub price: u128,
+}
+
+impl UsageReportPayload {
+    /// `borsh(USAGE_REPORT_TAG) || USAGE_REPORT_PAYLOAD_VERSION || borsh(self)`
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        return signing_bytes(USAGE_REPORT_TAG, USAGE_REPORT_PAYLOAD_VERSION, self);
+    }
+
+    /// SHA-256 of `signing_bytes()`. This is the message that gets signed.
+    pub fn hash(&self) -> CryptoHash {
+        return signing_hash(USAGE_REPORT_TAG, USAGE_REPORT_PAYLOAD_VERSION, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> ReviewRequestPayload {
+        ReviewRequestPayload {
+            contract_id: "core.near".parse().unwrap(),
+            vapi_id: "alice-vapi".to_string(),
+            vapi_version: "1.0".to_string(),
+            coder_id: "alice.near".parse().unwrap(),
+            reviewer_ids: vec!["bob.near".parse().unwrap()],
+            royalty_amounts: vec![10],
+            nonce: 7,
+            expires_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn hash_commits_to_every_field() {
+        let original = payload().hash();
+
+        let mut other = payload();
+        other.nonce += 1;
+        assert_ne!(original, other.hash());
+
+        let mut other = payload();
+        other.contract_id = "other-core.near".parse().unwrap();
+        assert_ne!(original, other.hash());
+
+        let mut other = payload();
+        other.royalty_amounts = vec![11];
+        assert_ne!(original, other.hash());
+    }
+
+    #[test]
+    fn usage_report_is_not_a_review_request() {
+        let report = UsageReportPayload {
+            contract_id: "core.near".parse().unwrap(),
+            gateway_id: "gateway.near".parse().unwrap(),
+            vapi_id: "alice-vapi".to_string(),
+            period_start: 1,
+            period_end: 2,
+            calls: 10,
+            price: 5,
+        };
+        let bytes = report.signing_bytes();
+        assert_eq!(&bytes[..4], &USAGE_REPORT_TAG.to_le_bytes());
+        assert_ne!(&bytes[..4], &REVIEW_REQUEST_TAG.to_le_bytes());
+
+        let mut repriced = report.clone();
+        repriced.price += 1;
+        assert_ne!(report.hash(), repriced.hash());
+    }
+}
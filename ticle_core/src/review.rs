@@ -0,0 +1,441 @@
+use crate::*;
+
+const ONE_DAY: u64 = 60 * 60 * 24 * 1_000_000_000;
+
+/// How long a reviewer has to accept a request.
+pub const ACCEPT_TIMEOUT: u64 = 7 * ONE_DAY;
+
+/// How long a reviewer has to deliver an accepted review.
+pub const SUBMISSION_TIMEOUT: u64 = 14 * ONE_DAY;
+
+/// How long the coder has to approve or dispute a submitted review. Once it passes
+/// the review counts as approved and the reviewer can claim the royalty.
+pub const APPROVAL_TIMEOUT: u64 = 14 * ONE_DAY;
+
+/// How long the owner has to resolve a dispute. Once it passes the dispute falls back
+/// to the silent approval of `APPROVAL_TIMEOUT` and the reviewer can claim the royalty.
+pub const DISPUTE_TIMEOUT: u64 = 30 * ONE_DAY;
+
+/// Lifecycle of one reviewer's review of a VAPI version.
+///
+/// `Paid` and `Cancelled` are only stored while the royalty transfer is in flight;
+/// the entry is removed once the transfer succeeds.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReviewStatus {
+    Requested,
+    Accepted,
+    Submitted,
+    Approved,
+    Disputed,
+    Paid,
+    Cancelled,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReviewActor {
+    Coder,
+    Reviewer,
+    Owner,
+}
+
+/// When, relative to the timeout of the current status, a transition may happen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReviewWindow {
+    Any,
+    BeforeTimeout,
+    AfterTimeout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReviewTransition {
+    pub actor: ReviewActor,
+    pub window: ReviewWindow,
+}
+
+impl ReviewStatus {
+    /// Time the review may stay in this status before the timed-out transitions open up.
+    pub fn timeout(&self) -> Option<u64> {
+        return match self {
+            ReviewStatus::Requested => Some(ACCEPT_TIMEOUT),
+            ReviewStatus::Accepted => Some(SUBMISSION_TIMEOUT),
+            ReviewStatus::Submitted => Some(APPROVAL_TIMEOUT),
+            ReviewStatus::Disputed => Some(DISPUTE_TIMEOUT),
+            _ => None,
+        };
+    }
+
+    /// Returns who may move a review from `self` to `next`, or `None` if the move is not allowed.
+    pub fn transition_to(&self, next: ReviewStatus) -> Option<ReviewTransition> {
+        use ReviewActor::*;
+        use ReviewStatus::*;
+        use ReviewWindow::*;
+
+        let (actor, window) = match (self, next) {
+            (Requested, Accepted) => (Reviewer, BeforeTimeout),
+            (Requested, Cancelled) => (Coder, Any),
+            (Accepted, Submitted) => (Reviewer, BeforeTimeout),
+            // 리뷰어가 기한 안에 리뷰를 제출하지 않은 경우
+            (Accepted, Cancelled) => (Coder, AfterTimeout),
+            (Submitted, Approved) => (Coder, Any),
+            (Submitted, Disputed) => (Coder, BeforeTimeout),
+            // 코더가 기한 안에 승인하지도 이의를 제기하지도 않은 경우
+            (Submitted, Paid) => (Reviewer, AfterTimeout),
+            (Approved, Paid) => (Reviewer, Any),
+            (Disputed, Approved) => (Owner, Any),
+            (Disputed, Cancelled) => (Owner, Any),
+            // owner가 기한 안에 분쟁을 해결하지 않은 경우
+            (Disputed, Paid) => (Reviewer, AfterTimeout),
+            _ => return None,
+        };
+        return Some(ReviewTransition { actor, window });
+    }
+}
+
+#[near]
+impl TicleCore {
+    pub fn accept_review(&mut self, vapi_id: String) {
+        let reviewer_id = env::predecessor_account_id();
+        self.internal_update_review(&vapi_id, &reviewer_id, ReviewStatus::Accepted);
+    }
+
+    pub fn submit_review(&mut self, vapi_id: String) {
+        let reviewer_id = env::predecessor_account_id();
+        self.internal_update_review(&vapi_id, &reviewer_id, ReviewStatus::Submitted);
+    }
+
+    pub fn approve_review(&mut self, vapi_id: String, reviewer_id: AccountId) {
+        self.internal_update_review(&vapi_id, &reviewer_id, ReviewStatus::Approved);
+    }
+
+    pub fn dispute_review(&mut self, vapi_id: String, reviewer_id: AccountId) {
+        self.internal_update_review(&vapi_id, &reviewer_id, ReviewStatus::Disputed);
+    }
+
+    /// Settles a disputed review: `approve` lets the reviewer claim the royalty,
+    /// otherwise the royalty goes back to the coder.
+    pub fn resolve_dispute(&mut self, vapi_id: String, reviewer_id: AccountId, approve: bool) -> PromiseOrValue<()> {
+        if approve {
+            self.internal_update_review(&vapi_id, &reviewer_id, ReviewStatus::Approved);
+            return PromiseOrValue::Value(());
+        }
+        return PromiseOrValue::Promise(self.internal_finalize_review(&vapi_id, &reviewer_id, ReviewStatus::Cancelled));
+    }
+
+    pub fn claim_review_reward(&mut self, vapi_id: String) -> Promise {
+        let reviewer_id = env::predecessor_account_id();
+        return self.internal_finalize_review(&vapi_id, &reviewer_id, ReviewStatus::Paid);
+    }
+
+    /// Refunds the royalties of reviews that were never accepted, or that were
+    /// accepted but not submitted in time.
+    ///
+    /// The royalty goes back to the coder. Before the review lifecycle existed a
+    /// cancelled royalty was sent to the reviewer; a reviewer who delivered can now
+    /// only be paid through `claim_review_reward`.
+    pub fn cancel_review(&mut self, vapi_id: String, reviewer_ids: Vec<AccountId>) -> Promise {
+        require!(!reviewer_ids.is_empty(), "At least one reviewer must be provided");
+        require!(reviewer_ids.len() <= 3, "Maximum 3 reviewers can be cancelled");
+
+        let mut combined_promise: Option<Promise> = None;
+        for reviewer_id in reviewer_ids.iter() {
+            let promise = self.internal_finalize_review(&vapi_id, reviewer_id, ReviewStatus::Cancelled);
+            if let Some(existing_promise) = combined_promise {
+                combined_promise = Some(existing_promise.and(promise));
+            } else {
+                combined_promise = Some(promise);
+            }
+        }
+
+        return combined_promise.unwrap();
+    }
+
+    #[private]
+    pub fn callback_finalize_review(&mut self, vapi_id: String, reviewer_id: AccountId, previous_status: ReviewStatus, previous_updated_at: U64) {
+        let initial_storage_usage = env::storage_usage();
+        let mut vapi = self.vapis.get(&vapi_id).expect("VAPI not found");
+        let mut reviewer_info = vapi.reviewer_infos.get(&reviewer_id).expect("Reviewer not found");
+
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                vapi.reviewer_infos.remove(&reviewer_id);
+                self.vapis.insert(&vapi_id, &vapi);
+                // 리뷰 항목의 스토리지 비용은 리뷰를 요청한 코더가 냈다.
+                self.internal_update_storage(&vapi.coder_info.account_id, initial_storage_usage);
+
+                if reviewer_info.status == ReviewStatus::Paid {
+                    EventLogVariant::ReviewRewardClaim(vec![ReviewRewardClaimLog {
+                        vapi_id,
+                        reviewer_id,
+                        amount: U128(reviewer_info.royalty_amount),
+                    }]).emit();
+                } else {
+                    EventLogVariant::ReviewCancel(vec![ReviewCancelLog {
+                        vapi_id,
+                        coder_id: vapi.coder_info.account_id,
+                        reviewer_ids: vec![reviewer_id],
+                    }]).emit();
+                }
+            }
+            _ => {
+                log!("[callback_finalize_review] ft_transfer failed, restoring {:?}", previous_status);
+                // 시각도 되돌려야 실패한 전송 때문에 기한이 다시 시작되지 않는다.
+                reviewer_info.status = previous_status;
+                reviewer_info.updated_at = previous_updated_at.0;
+                vapi.reviewer_infos.insert(&reviewer_id, &reviewer_info);
+                self.vapis.insert(&vapi_id, &vapi);
+            }
+        }
+    }
+
+    pub fn get_review(&self, vapi_id: String, reviewer_id: AccountId) -> Option<ReviewerInfo> {
+        let vapi = self.internal_get_vapi(&vapi_id);
+        return vapi.reviewer_infos.get(&reviewer_id);
+    }
+}
+
+impl TicleCore {
+    /// Checks that the predecessor may move the review to `next` right now, and returns
+    /// the updated entry without saving it.
+    fn internal_transition_review(&self, vapi: &VAPI, reviewer_id: &AccountId, next: ReviewStatus) -> ReviewerInfo {
+        let mut reviewer_info = vapi.reviewer_infos.get(reviewer_id).expect("Reviewer not found");
+        let current = reviewer_info.status;
+        let transition = current
+            .transition_to(next)
+            .unwrap_or_else(|| env::panic_str(&format!("Review can't move from {:?} to {:?}", current, next)));
+
+        let actor_id = env::predecessor_account_id();
+        let allowed = match transition.actor {
+            ReviewActor::Coder => vapi.coder_info.account_id == actor_id,
+            ReviewActor::Reviewer => *reviewer_id == actor_id,
+            ReviewActor::Owner => self.owner_id == actor_id,
+        };
+        require!(allowed, format!("Only {:?} can move a review to {:?}", transition.actor, next));
+
+        let now = env::block_timestamp();
+        let timed_out = current.timeout().map(|timeout| now >= reviewer_info.updated_at + timeout).unwrap_or(false);
+        match transition.window {
+            ReviewWindow::Any => {}
+            ReviewWindow::BeforeTimeout => require!(!timed_out, format!("Review has been {:?} for too long", current)),
+            ReviewWindow::AfterTimeout => require!(timed_out, format!("Review has not been {:?} long enough", current)),
+        }
+
+        reviewer_info.status = next;
+        reviewer_info.updated_at = now;
+        return reviewer_info;
+    }
+
+    fn internal_update_review(&mut self, vapi_id: &String, reviewer_id: &AccountId, next: ReviewStatus) {
+        let mut vapi = self.internal_get_vapi(vapi_id);
+        let previous_status = vapi.reviewer_infos.get(reviewer_id).expect("Reviewer not found").status;
+        let reviewer_info = self.internal_transition_review(&vapi, reviewer_id, next);
+        vapi.reviewer_infos.insert(reviewer_id, &reviewer_info);
+        self.vapis.insert(vapi_id, &vapi);
+
+        EventLogVariant::ReviewStatusUpdate(vec![ReviewStatusUpdateLog {
+            vapi_id: vapi_id.clone(),
+            reviewer_id: reviewer_id.clone(),
+            old_status: previous_status,
+            new_status: next,
+        }]).emit();
+    }
+
+    /// Moves the review to `Paid` or `Cancelled` and sends the royalty to the reviewer
+    /// or back to the coder. The entry is removed by `callback_finalize_review`.
+    fn internal_finalize_review(&mut self, vapi_id: &String, reviewer_id: &AccountId, next: ReviewStatus) -> Promise {
+        let mut vapi = self.internal_get_vapi(vapi_id);
+        let previous = vapi.reviewer_infos.get(reviewer_id).expect("Reviewer not found");
+        let reviewer_info = self.internal_transition_review(&vapi, reviewer_id, next);
+        vapi.reviewer_infos.insert(reviewer_id, &reviewer_info);
+        self.vapis.insert(vapi_id, &vapi);
+
+        let receiver_id = if next == ReviewStatus::Paid {
+            reviewer_id.clone()
+        } else {
+            vapi.coder_info.account_id.clone()
+        };
+        log!("[internal_finalize_review] {} -> {}: {}", reviewer_id, receiver_id, reviewer_info.royalty_amount);
+
+        return ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(20))
+            .ft_transfer(receiver_id, U128(reviewer_info.royalty_amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(20))
+                    .callback_finalize_review(vapi_id.clone(), reviewer_id.clone(), previous.status, U64(previous.updated_at))
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig};
+
+    const VAPI_ID: &str = "vapi";
+    const DISPUTED_AT: u64 = 1_000;
+
+    fn set_context(predecessor_id: AccountId, block_timestamp: u64, promise_results: Vec<PromiseResult>) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(5))
+                .predecessor_account_id(predecessor_id)
+                .block_timestamp(block_timestamp)
+                .build(),
+            near_sdk::test_vm_config(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            promise_results
+        );
+    }
+
+    /// A contract in which `accounts(2)`'s review of `accounts(1)`'s VAPI moved to
+    /// `status` at `updated_at`.
+    fn contract_with_review(status: ReviewStatus, updated_at: u64) -> TicleCore {
+        set_context(accounts(0), updated_at, vec![]);
+        let mut contract = TicleCore::new("token.near".parse().unwrap(), accounts(0));
+        let mut vapi = VAPI::new(VAPI_ID, accounts(1));
+        vapi.reviewer_infos.insert(&accounts(2), &ReviewerInfo {
+            version: "1.0".to_string(),
+            royalty_amount: 10,
+            timestamp: 0,
+            status,
+            updated_at,
+        });
+        contract.vapis.insert(&VAPI_ID.to_string(), &vapi);
+        return contract;
+    }
+
+    fn disputed_contract() -> TicleCore {
+        return contract_with_review(ReviewStatus::Disputed, DISPUTED_AT);
+    }
+
+    /// Receivers of the `ft_transfer` calls created so far.
+    fn ft_transfer_receivers() -> Vec<AccountId> {
+        return near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                MockAction::FunctionCallWeight { method_name, args, .. } if method_name == b"ft_transfer" => {
+                    let args: near_sdk::serde_json::Value = near_sdk::serde_json::from_slice(&args).unwrap();
+                    Some(args["receiver_id"].as_str().unwrap().parse().unwrap())
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    #[test]
+    fn reviewer_drives_the_happy_path() {
+        let steps = [
+            (ReviewStatus::Requested, ReviewStatus::Accepted),
+            (ReviewStatus::Accepted, ReviewStatus::Submitted),
+            (ReviewStatus::Approved, ReviewStatus::Paid),
+        ];
+        for (from, to) in steps {
+            assert_eq!(from.transition_to(to).unwrap().actor, ReviewActor::Reviewer);
+        }
+        assert_eq!(
+            ReviewStatus::Submitted.transition_to(ReviewStatus::Approved).unwrap().actor,
+            ReviewActor::Coder
+        );
+    }
+
+    #[test]
+    fn timeouts_gate_cancellation_and_silent_approval() {
+        let cancel_accepted = ReviewStatus::Accepted.transition_to(ReviewStatus::Cancelled).unwrap();
+        assert_eq!(cancel_accepted.window, ReviewWindow::AfterTimeout);
+
+        let silent_approval = ReviewStatus::Submitted.transition_to(ReviewStatus::Paid).unwrap();
+        assert_eq!(silent_approval, ReviewTransition { actor: ReviewActor::Reviewer, window: ReviewWindow::AfterTimeout });
+
+        let dispute = ReviewStatus::Submitted.transition_to(ReviewStatus::Disputed).unwrap();
+        assert_eq!(dispute.window, ReviewWindow::BeforeTimeout);
+    }
+
+    #[test]
+    fn terminal_and_skipped_transitions_are_rejected() {
+        assert!(ReviewStatus::Requested.transition_to(ReviewStatus::Paid).is_none());
+        assert!(ReviewStatus::Submitted.transition_to(ReviewStatus::Cancelled).is_none());
+        assert!(ReviewStatus::Paid.transition_to(ReviewStatus::Cancelled).is_none());
+        assert!(ReviewStatus::Cancelled.transition_to(ReviewStatus::Requested).is_none());
+        assert_eq!(
+            ReviewStatus::Disputed.transition_to(ReviewStatus::Cancelled).unwrap().actor,
+            ReviewActor::Owner
+        );
+    }
+
+    #[test]
+    fn reviewer_is_paid_once_a_dispute_times_out() {
+        let dispute_paid = ReviewStatus::Disputed.transition_to(ReviewStatus::Paid).unwrap();
+        assert_eq!(dispute_paid, ReviewTransition { actor: ReviewActor::Reviewer, window: ReviewWindow::AfterTimeout });
+        assert_eq!(ReviewStatus::Disputed.timeout(), Some(DISPUTE_TIMEOUT));
+
+        let mut contract = disputed_contract();
+        set_context(accounts(2), DISPUTED_AT + DISPUTE_TIMEOUT, vec![]);
+        contract.claim_review_reward(VAPI_ID.to_string());
+        assert_eq!(contract.get_review(VAPI_ID.to_string(), accounts(2)).unwrap().status, ReviewStatus::Paid);
+    }
+
+    #[test]
+    #[should_panic(expected = "Review has not been Disputed long enough")]
+    fn open_dispute_blocks_the_claim() {
+        let mut contract = disputed_contract();
+        set_context(accounts(2), DISPUTED_AT + DISPUTE_TIMEOUT - 1, vec![]);
+        contract.claim_review_reward(VAPI_ID.to_string());
+    }
+
+    #[test]
+    fn failed_transfer_restores_status_and_timestamp() {
+        let mut contract = disputed_contract();
+        let claimed_at = DISPUTED_AT + DISPUTE_TIMEOUT;
+        set_context(accounts(2), claimed_at, vec![]);
+        contract.claim_review_reward(VAPI_ID.to_string());
+
+        set_context(accounts(5), claimed_at, vec![PromiseResult::Failed]);
+        contract.callback_finalize_review(VAPI_ID.to_string(), accounts(2), ReviewStatus::Disputed, U64(DISPUTED_AT));
+        let review = contract.get_review(VAPI_ID.to_string(), accounts(2)).unwrap();
+        assert_eq!(review.status, ReviewStatus::Disputed);
+        assert_eq!(review.updated_at, DISPUTED_AT);
+
+        // 기한은 처음 분쟁이 제기된 때부터 계속 흐르므로 바로 다시 청구할 수 있다.
+        set_context(accounts(2), claimed_at, vec![]);
+        contract.claim_review_reward(VAPI_ID.to_string());
+    }
+
+    #[test]
+    fn cancelled_request_is_refunded_to_the_coder() {
+        // 수락되지 않은 리뷰는 코더가 언제든 취소할 수 있다.
+        let mut contract = contract_with_review(ReviewStatus::Requested, DISPUTED_AT);
+        set_context(accounts(1), DISPUTED_AT, vec![]);
+        contract.cancel_review(VAPI_ID.to_string(), vec![accounts(2)]);
+        assert_eq!(ft_transfer_receivers(), vec![accounts(1)]);
+    }
+
+    #[test]
+    fn timed_out_accepted_review_is_refunded_to_the_coder() {
+        let mut contract = contract_with_review(ReviewStatus::Accepted, DISPUTED_AT);
+        set_context(accounts(1), DISPUTED_AT + SUBMISSION_TIMEOUT, vec![]);
+        contract.cancel_review(VAPI_ID.to_string(), vec![accounts(2)]);
+        assert_eq!(ft_transfer_receivers(), vec![accounts(1)]);
+    }
+
+    #[test]
+    fn rejected_dispute_is_refunded_to_the_coder() {
+        let mut contract = disputed_contract();
+        set_context(accounts(0), DISPUTED_AT, vec![]);
+        contract.resolve_dispute(VAPI_ID.to_string(), accounts(2), false);
+        assert_eq!(ft_transfer_receivers(), vec![accounts(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Review has not been Accepted long enough")]
+    fn accepted_review_cannot_be_cancelled_before_the_timeout() {
+        let mut contract = contract_with_review(ReviewStatus::Accepted, DISPUTED_AT);
+        set_context(accounts(1), DISPUTED_AT + SUBMISSION_TIMEOUT - 1, vec![]);
+        contract.cancel_review(VAPI_ID.to_string(), vec![accounts(2)]);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::*;
+
+const ONE_DAY: u64 = 60 * 60 * 24 * 1_000_000_000;
+
+/// Longest window the owner may spread a settlement's delegator share over.
+pub const MAX_REWARD_RELEASE_PERIOD: u64 = 90 * ONE_DAY;
+
+/// Release window a new VAPI starts with, so a deposit made right before a settlement
+/// doesn't take a full share of it.
+pub const DEFAULT_REWARD_RELEASE_PERIOD: u64 = 7 * ONE_DAY;
+
+/// Scale of `acc_reward_per_share`.
+pub(crate) const ACC_REWARD_PRECISION: Balance = 1_000_000_000_000;
+
+/// What a settlement does with the delegator share of a VAPI that has no stake.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZeroStakePolicy {
+    /// Credit it to the coder together with the coder share.
+    Coder,
+    /// Keep it in the VAPI's reward schedule; it is released to the first delegators.
+    Undistributed,
+    /// Credit it to the protocol treasury.
+    Treasury,
+}
+
+#[near]
+impl TicleCore {
+    pub fn set_zero_stake_policy(&mut self, policy: ZeroStakePolicy) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can set the zero stake policy");
+        self.zero_stake_policy = policy;
+        log!("[set_zero_stake_policy] {:?}", policy);
+    }
+
+    pub fn get_zero_stake_policy(&self) -> ZeroStakePolicy {
+        return self.zero_stake_policy;
+    }
+
+    /// Sets the window (in nanoseconds) over which the delegator share of each settlement
+    /// of `vapi_id` is released. With `0` the share is credited in full at settlement,
+    /// so a deposit made right before a settlement earns as much as one held all along.
+    pub fn set_reward_release_period(&mut self, vapi_id: String, reward_release_period: U64) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can set the reward release period");
+        require!(reward_release_period.0 <= MAX_REWARD_RELEASE_PERIOD, "Reward release period is too long");
+
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        internal_release_rewards(&mut vapi.delegation_info);
+        vapi.delegation_info.reward_release_period = reward_release_period.0;
+        self.vapis.insert(&vapi_id, &vapi);
+        log!("[set_reward_release_period] {}: {}", vapi_id, reward_release_period.0);
+    }
+
+    pub fn get_reward_release_period(&self, vapi_id: String) -> U64 {
+        return U64(self.internal_get_vapi(&vapi_id).delegation_info.reward_release_period);
+    }
+
+    /// Delegator rewards of `vapi_id` that were settled but not released yet.
+    pub fn unreleased_reward(&self, vapi_id: String) -> U128 {
+        let mut delegation_info = self.internal_get_vapi(&vapi_id).delegation_info;
+        internal_release_rewards(&mut delegation_info);
+        return U128(delegation_info.unreleased_reward_amount);
+    }
+}
+
+/// Credits the part of `unreleased_reward_amount` that vested since the last release to
+/// `acc_reward_per_share`. Must run before anything changes `total_deposit_amount` or a
+/// delegator's deposit, so each release is shared by the stake that was there for it.
+///
+/// Nothing is released while the pool is empty, so the elapsed time carries over to the
+/// first delegators.
+pub(crate) fn internal_release_rewards(delegation_info: &mut DelegationInfo) {
+    let now = env::block_timestamp();
+    if delegation_info.unreleased_reward_amount == 0 || delegation_info.total_deposit_amount == 0 {
+        return;
+    }
+
+    let released_amount = if now >= delegation_info.reward_release_end {
+        delegation_info.unreleased_reward_amount
+    } else {
+        let elapsed = (now - delegation_info.last_reward_release_at) as u128;
+        let remaining = (delegation_info.reward_release_end - delegation_info.last_reward_release_at) as u128;
+        delegation_info.unreleased_reward_amount * elapsed / remaining
+    };
+
+    delegation_info.acc_reward_per_share += released_amount * ACC_REWARD_PRECISION / delegation_info.total_deposit_amount;
+    delegation_info.unreleased_reward_amount -= released_amount;
+    delegation_info.last_reward_release_at = now;
+}
+
+/// Adds a settlement's delegator share to the release schedule. Whatever is still
+/// unreleased is spread, together with `amount`, over a fresh window.
+pub(crate) fn internal_schedule_rewards(delegation_info: &mut DelegationInfo, amount: Balance) {
+    internal_release_rewards(delegation_info);
+
+    let now = env::block_timestamp();
+    delegation_info.unreleased_reward_amount += amount;
+    delegation_info.last_reward_release_at = now;
+    delegation_info.reward_release_end = now + delegation_info.reward_release_period;
+
+    internal_release_rewards(delegation_info);
+}
+
+/// Reward `delegator_info` has earned but not claimed, given the already released `acc_reward_per_share`.
+pub(crate) fn internal_pending_reward(delegator_info: &Delegator, acc_reward_per_share: Balance) -> Balance {
+    return (delegator_info.deposit_amount * acc_reward_per_share / ACC_REWARD_PRECISION).saturating_sub(delegator_info.reward_debt);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const SETTLED_AT: u64 = 1_000;
+
+    fn set_block_timestamp(block_timestamp: u64) {
+        testing_env!(VMContextBuilder::new().block_timestamp(block_timestamp).build());
+    }
+
+    #[test]
+    fn settlement_is_released_over_the_default_period() {
+        set_block_timestamp(SETTLED_AT);
+        let mut delegation_info = VAPI::new("vapi", accounts(1)).delegation_info;
+        assert_eq!(delegation_info.reward_release_period, DEFAULT_REWARD_RELEASE_PERIOD);
+        delegation_info.total_deposit_amount = 100;
+
+        internal_schedule_rewards(&mut delegation_info, 1_000);
+        assert_eq!(delegation_info.unreleased_reward_amount, 1_000);
+        assert_eq!(delegation_info.acc_reward_per_share, 0);
+
+        // 기간의 절반이 지나면 절반만 풀린다.
+        set_block_timestamp(SETTLED_AT + DEFAULT_REWARD_RELEASE_PERIOD / 2);
+        internal_release_rewards(&mut delegation_info);
+        assert_eq!(delegation_info.unreleased_reward_amount, 500);
+        assert_eq!(delegation_info.acc_reward_per_share, 500 * ACC_REWARD_PRECISION / 100);
+
+        set_block_timestamp(SETTLED_AT + DEFAULT_REWARD_RELEASE_PERIOD);
+        internal_release_rewards(&mut delegation_info);
+        assert_eq!(delegation_info.unreleased_reward_amount, 0);
+        assert_eq!(delegation_info.acc_reward_per_share, 1_000 * ACC_REWARD_PRECISION / 100);
+    }
+}
+
@@ -0,0 +1,512 @@
+use near_sdk::json_types::Base58CryptoHash;
+
+use crate::*;
+use crate::merkle::{verify_merkle_proof, SettlementLeaf};
+
+/// VAPIs applied by the `ft_transfer_call` that registers a batch. The rest is left
+/// for `process_settlement`.
+pub const SETTLEMENT_PAGE_SIZE: u64 = 20;
+
+/// Gas one VAPI of a batch needs, with some headroom.
+const GAS_FOR_SETTLEMENT_ENTRY: Gas = Gas::from_tgas(5);
+
+/// Gas kept for scheduling the burn and its callback once the last page is applied.
+const GAS_FOR_SETTLEMENT_BURN: Gas = Gas::from_tgas(40);
+
+#[near(serializers = [borsh])]
+pub struct SettlementEntry {
+    vapi_id: String,
+    amount: Balance,
+}
+
+/// A registered settlement. Its tokens are held by `TicleCore` until every entry has
+/// been applied; `cursor` is the index of the next entry to apply.
+#[near(serializers = [borsh])]
+pub struct SettlementBatch {
+    period_start: u64,
+    period_end: u64,
+    entries: Vector<SettlementEntry>,
+    // entries는 처리가 끝나면 비우므로 항목 수를 따로 기록한다.
+    entry_count: u64,
+    cursor: u64,
+    total_amount: Balance,
+    burn_amount: Balance,
+}
+
+#[near(serializers = [json])]
+pub struct SettlementBatchView {
+    period_start: U64,
+    period_end: U64,
+    total_amount: U128,
+    burn_amount: U128,
+    processed: U64,
+    total_entries: U64,
+    completed: bool,
+}
+
+/// Longest Merkle proof accepted by `claim_settlement`, enough for 2^32 VAPIs.
+const MAX_MERKLE_PROOF_LENGTH: usize = 32;
+
+/// Revenue of every VAPI for one period, committed to as a Merkle root of
+/// `SettlementLeaf`s. Its tokens are held by `TicleCore` until each VAPI claims its part.
+#[near(serializers = [borsh])]
+pub struct SettlementEpoch {
+    merkle_root: CryptoHash,
+    period_start: u64,
+    period_end: u64,
+    total_amount: Balance,
+    claimed_amount: Balance,
+    claimed_vapis: LookupSet<String>,
+}
+
+#[near(serializers = [json])]
+pub struct SettlementEpochView {
+    merkle_root: Base58CryptoHash,
+    period_start: U64,
+    period_end: U64,
+    total_amount: U128,
+    claimed_amount: U128,
+}
+
+/// One VAPI's part of a settlement batch, kept so off-chain billing can reconcile
+/// against the chain.
+#[near(serializers = [borsh])]
+pub struct SettlementRecord {
+    batch_id: String,
+    /// Usage period the batch covers, as block timestamps in nanoseconds.
+    period_start: u64,
+    period_end: u64,
+    amount: Balance,
+    coder_amount: Balance,
+    delegator_amount: Balance,
+    burn_amount: Balance,
+    treasury_amount: Balance,
+    settled_at: u64,
+}
+
+#[near(serializers = [json])]
+pub struct SettlementRecordView {
+    batch_id: String,
+    period_start: U64,
+    period_end: U64,
+    amount: U128,
+    coder_amount: U128,
+    delegator_amount: U128,
+    burn_amount: U128,
+    treasury_amount: U128,
+    settled_at: U64,
+}
+
+impl From<SettlementRecord> for SettlementRecordView {
+    fn from(record: SettlementRecord) -> Self {
+        Self {
+            batch_id: record.batch_id,
+            period_start: U64(record.period_start),
+            period_end: U64(record.period_end),
+            amount: U128(record.amount),
+            coder_amount: U128(record.coder_amount),
+            delegator_amount: U128(record.delegator_amount),
+            burn_amount: U128(record.burn_amount),
+            treasury_amount: U128(record.treasury_amount),
+            settled_at: U64(record.settled_at),
+        }
+    }
+}
+
+#[near]
+impl TicleCore {
+    /// Applies up to `limit` more VAPIs of a registered batch, as many as the attached
+    /// gas allows. Anyone may call it; the batch content was fixed by the owner when it
+    /// was registered. Sends the batch's burn to the token contract after the last page.
+    pub fn process_settlement(&mut self, batch_id: String, limit: Option<U64>) -> PromiseOrValue<()> {
+        let batch = self.settlement_batches.get(&batch_id).expect("Settlement batch not found");
+        require!(batch.cursor < batch.entry_count, "Settlement batch already completed");
+
+        let (processed, burn) = self.internal_process_settlement(&batch_id, limit.map_or(SETTLEMENT_PAGE_SIZE, |limit| limit.0));
+        require!(processed > 0, "Not enough gas to process a settlement entry");
+
+        return match burn {
+            Some(promise) => PromiseOrValue::Promise(promise),
+            None => PromiseOrValue::Value(()),
+        };
+    }
+
+    /// Applies the fee split to what `vapi_id` earned in `epoch_id`, proven against the
+    /// epoch's Merkle root. Anyone may call it, e.g. the coder or a keeper; the proof
+    /// fixes the amount. Sends the burn share to the token contract.
+    pub fn claim_settlement(&mut self, epoch_id: String, vapi_id: String, amount: U128, proof: Vec<Base58CryptoHash>) -> PromiseOrValue<()> {
+        let mut epoch = self.settlement_epochs.get(&epoch_id).expect("Settlement epoch not found");
+        require!(amount.0 > 0, "Nothing to claim");
+        require!(proof.len() <= MAX_MERKLE_PROOF_LENGTH, "Merkle proof is too long");
+        require!(!epoch.claimed_vapis.contains(&vapi_id), "Settlement already claimed");
+
+        let leaf = SettlementLeaf { vapi_id: vapi_id.clone(), amount: amount.0 };
+        let proof: Vec<CryptoHash> = proof.into_iter().map(CryptoHash::from).collect();
+        require!(verify_merkle_proof(&leaf, &proof, &epoch.merkle_root), "Invalid Merkle proof");
+        require!(epoch.claimed_amount + amount.0 <= epoch.total_amount, "Claims exceed the epoch total");
+
+        require!(!self.legacy_vapis.contains(&vapi_id), "VAPI storage must be migrated first");
+        require!(self.vapis.contains_key(&vapi_id), format!("VAPI not found: {}", vapi_id));
+        // epoch는 등록된 batch와 겹치지 않게만 올라오고, batch는 epoch 이후 기간만
+        // 등록할 수 있으므로 정상적으로는 통과한다.
+        let settled_until = self.settled_until.get(&vapi_id).unwrap_or(0);
+        require!(epoch.period_start >= settled_until, format!("Settlement period overlaps the previous one of {}", vapi_id));
+
+        epoch.claimed_vapis.insert(&vapi_id);
+        epoch.claimed_amount += amount.0;
+        self.settlement_epochs.insert(&epoch_id, &epoch);
+
+        log!("[claim_settlement] {}: {} {}", epoch_id, vapi_id, amount.0);
+        return self.internal_apply_settlement(vapi_id, &epoch_id, epoch.period_start, epoch.period_end, amount.0);
+    }
+
+    pub fn get_settlement_epoch(&self, epoch_id: String) -> Option<SettlementEpochView> {
+        return self.settlement_epochs.get(&epoch_id).map(|epoch| SettlementEpochView {
+            merkle_root: epoch.merkle_root.into(),
+            period_start: U64(epoch.period_start),
+            period_end: U64(epoch.period_end),
+            total_amount: U128(epoch.total_amount),
+            claimed_amount: U128(epoch.claimed_amount),
+        });
+    }
+
+    /// Sends the burns that failed, e.g. while the token was paused, to the token again.
+    pub fn retry_burn(&mut self) -> Promise {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can retry the burn");
+        let amount = self.pending_burn_amount;
+        require!(amount > 0, "Nothing to burn");
+        self.pending_burn_amount = 0;
+        return self.internal_burn(amount);
+    }
+
+    #[private]
+    pub fn callback_burn(&mut self, amount: U128) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                EventLogVariant::Burn(vec![BurnLog { amount }]).emit();
+            }
+            _ => {
+                log!("[callback_burn] burn failed, keeping {} for retry_burn", amount.0);
+                self.pending_burn_amount += amount.0;
+            }
+        }
+    }
+
+    /// Burn share of settlements that the token failed to burn and `retry_burn` will send again.
+    pub fn get_pending_burn(&self) -> U128 {
+        return U128(self.pending_burn_amount);
+    }
+
+    pub fn is_settlement_claimed(&self, epoch_id: String, vapi_id: String) -> bool {
+        return self.settlement_epochs.get(&epoch_id).is_some_and(|epoch| epoch.claimed_vapis.contains(&vapi_id));
+    }
+
+    pub fn is_settlement_batch_used(&self, batch_id: String) -> bool {
+        return self.settlement_batches.contains_key(&batch_id);
+    }
+
+    pub fn get_settlement_batch(&self, batch_id: String) -> Option<SettlementBatchView> {
+        return self.settlement_batches.get(&batch_id).map(|batch| SettlementBatchView {
+            period_start: U64(batch.period_start),
+            period_end: U64(batch.period_end),
+            total_amount: U128(batch.total_amount),
+            burn_amount: U128(batch.burn_amount),
+            processed: U64(batch.cursor),
+            total_entries: U64(batch.entry_count),
+            completed: batch.cursor == batch.entry_count,
+        });
+    }
+
+    /// Settlements of `vapi_id`, oldest first.
+    pub fn get_settlement_history(&self, vapi_id: String, from_index: Option<U64>, limit: Option<U64>) -> Vec<SettlementRecordView> {
+        let vapi = self.internal_get_vapi(&vapi_id);
+        let from_index = from_index.map_or(0, |index| index.0);
+        let limit = limit.map_or(50, |limit| limit.0);
+        return (from_index..std::cmp::min(from_index.saturating_add(limit), vapi.settlements.len()))
+            .filter_map(|index| vapi.settlements.get(index))
+            .map(SettlementRecordView::from)
+            .collect();
+    }
+}
+
+impl TicleCore {
+    /// Registers a settlement batch and escrows the transferred tokens. `batch_id` may only
+    /// be used once, so the owner's automation can safely resend a batch whose result it
+    /// did not see. Every VAPI is checked and its period reserved here, so applying the
+    /// batch later can't fail halfway.
+    pub(crate) fn internal_register_settlement(
+        &mut self,
+        sender_id: &AccountId,
+        batch_id: String,
+        period_start: u64,
+        period_end: u64,
+        entries: Vec<(String, U128)>,
+        transfer_amount: U128,
+    ) -> String {
+        log!("[internal_register_settlement] {}", batch_id);
+        require!(*sender_id == self.owner_id, "Only owner can settle");
+        require!(!batch_id.is_empty(), "batch_id is required");
+        require!(period_start < period_end, "period_start must be earlier than period_end");
+        require!(!self.settlement_batches.contains_key(&batch_id), "Settlement batch already processed");
+        require!(!self.settlement_epochs.contains_key(&batch_id), "Settlement batch already processed");
+        require!(period_start >= self.epoch_settled_until, "Settlement period overlaps a settlement epoch");
+
+        let mut batch = SettlementBatch {
+            period_start,
+            period_end,
+            entries: Vector::new(StorageKey::SettlementBatchEntries { batch_hash: env::sha256_array(batch_id.as_bytes()) }),
+            entry_count: 0,
+            cursor: 0,
+            total_amount: 0,
+            burn_amount: 0,
+        };
+        for (vapi_id, amount) in entries {
+            require!(!self.legacy_vapis.contains(&vapi_id), "VAPI storage must be migrated first");
+            require!(self.vapis.contains_key(&vapi_id), format!("VAPI not found: {}", vapi_id));
+            let settled_until = self.settled_until.get(&vapi_id).unwrap_or(0);
+            require!(period_start >= settled_until, format!("Settlement period overlaps the previous one of {}", vapi_id));
+            self.settled_until.insert(&vapi_id, &period_end);
+
+            batch.total_amount += amount.0;
+            batch.entries.push(&SettlementEntry { vapi_id, amount: amount.0 });
+        }
+        batch.entry_count = batch.entries.len();
+        require!(batch.total_amount == transfer_amount.0, "Settlement amounts must add up to the transferred amount");
+        self.batch_settled_until = std::cmp::max(self.batch_settled_until, period_end);
+
+        self.settlement_batches.insert(&batch_id, &batch);
+        return batch_id;
+    }
+
+    /// Applies up to `limit` entries starting at the batch cursor and returns how many were
+    /// applied, plus the burn promise if the batch is now complete.
+    pub(crate) fn internal_process_settlement(&mut self, batch_id: &String, limit: u64) -> (u64, Option<Promise>) {
+        let mut batch = self.settlement_batches.get(batch_id).expect("Settlement batch not found");
+
+        let mut settlement_logs: Vec<SettlementLog> = Vec::new();
+        let mut treasury_logs: Vec<TreasuryDepositLog> = Vec::new();
+        let mut processed: u64 = 0;
+        while processed < limit && batch.cursor < batch.entry_count {
+            if env::prepaid_gas().saturating_sub(env::used_gas()) < GAS_FOR_SETTLEMENT_ENTRY.saturating_add(GAS_FOR_SETTLEMENT_BURN) {
+                log!("[internal_process_settlement] stopping early to save gas");
+                break;
+            }
+
+            let entry = batch.entries.get(batch.cursor).unwrap();
+            let record = self.internal_settle_vapi(&entry.vapi_id, batch_id, batch.period_start, batch.period_end, entry.amount);
+            batch.burn_amount += record.burn_amount;
+            if record.treasury_amount > 0 {
+                treasury_logs.push(TreasuryDepositLog {
+                    vapi_id: entry.vapi_id.clone(),
+                    amount: U128(record.treasury_amount),
+                });
+            }
+            settlement_logs.push(SettlementLog {
+                vapi_id: entry.vapi_id,
+                amount: U128(record.amount),
+                coder_amount: U128(record.coder_amount),
+                delegator_amount: U128(record.delegator_amount),
+                burn_amount: U128(record.burn_amount),
+            });
+
+            batch.cursor += 1;
+            processed += 1;
+        }
+        log!("[internal_process_settlement] {}: {}/{}", batch_id, batch.cursor, batch.entry_count);
+
+        if !settlement_logs.is_empty() {
+            EventLogVariant::Settlement(settlement_logs).emit();
+        }
+        if !treasury_logs.is_empty() {
+            EventLogVariant::TreasuryDeposit(treasury_logs).emit();
+        }
+
+        let completed = batch.cursor == batch.entry_count;
+        if completed && processed > 0 {
+            // 처리가 끝난 항목은 더 이상 필요 없으므로 storage를 돌려받는다.
+            batch.entries.clear();
+        }
+        self.settlement_batches.insert(batch_id, &batch);
+
+        if !completed || processed == 0 {
+            return (processed, None);
+        }
+
+        return (processed, Some(self.internal_burn(batch.burn_amount)));
+    }
+
+    /// Posts the Merkle root of an epoch's per-VAPI revenue and escrows the transferred
+    /// tokens, which must be the sum of every leaf. Epochs follow each other: one may
+    /// not start before the previous one ended, nor before any registered batch ended,
+    /// since a VAPI settled by that batch could never claim its leaf.
+    pub(crate) fn internal_post_settlement_epoch(
+        &mut self,
+        sender_id: &AccountId,
+        epoch_id: String,
+        period_start: u64,
+        period_end: u64,
+        merkle_root: CryptoHash,
+        transfer_amount: U128,
+    ) {
+        log!("[internal_post_settlement_epoch] {}", epoch_id);
+        require!(*sender_id == self.owner_id, "Only owner can settle");
+        require!(!epoch_id.is_empty(), "epoch_id is required");
+        require!(period_start < period_end, "period_start must be earlier than period_end");
+        require!(!self.settlement_epochs.contains_key(&epoch_id), "Settlement batch already processed");
+        require!(!self.settlement_batches.contains_key(&epoch_id), "Settlement batch already processed");
+        require!(period_start >= self.epoch_settled_until, "Settlement period overlaps the previous epoch");
+        require!(period_start >= self.batch_settled_until, "Settlement period overlaps a settlement batch");
+
+        let epoch = SettlementEpoch {
+            merkle_root,
+            period_start,
+            period_end,
+            total_amount: transfer_amount.0,
+            claimed_amount: 0,
+            claimed_vapis: LookupSet::new(StorageKey::SettlementEpochClaims { epoch_hash: env::sha256_array(epoch_id.as_bytes()) }),
+        };
+        self.settlement_epochs.insert(&epoch_id, &epoch);
+        self.epoch_settled_until = period_end;
+
+        EventLogVariant::SettlementEpoch(vec![SettlementEpochLog {
+            epoch_id,
+            merkle_root: merkle_root.into(),
+            period_start: U64(period_start),
+            period_end: U64(period_end),
+            total_amount: transfer_amount,
+        }]).emit();
+    }
+
+    /// Settles a single VAPI right away, emits its events and burns its burn share.
+    pub(crate) fn internal_apply_settlement(&mut self, vapi_id: String, batch_id: &str, period_start: u64, period_end: u64, amount: Balance) -> PromiseOrValue<()> {
+        let record = self.internal_settle_vapi(&vapi_id, batch_id, period_start, period_end, amount);
+        if record.treasury_amount > 0 {
+            EventLogVariant::TreasuryDeposit(vec![TreasuryDepositLog {
+                vapi_id: vapi_id.clone(),
+                amount: U128(record.treasury_amount),
+            }]).emit();
+        }
+        EventLogVariant::Settlement(vec![SettlementLog {
+            vapi_id,
+            amount: U128(amount),
+            coder_amount: U128(record.coder_amount),
+            delegator_amount: U128(record.delegator_amount),
+            burn_amount: U128(record.burn_amount),
+        }]).emit();
+
+        if record.burn_amount == 0 {
+            return PromiseOrValue::Value(());
+        }
+        return PromiseOrValue::Promise(self.internal_burn(record.burn_amount));
+    }
+
+    /// Burns `amount` of the tokens `TicleCore` holds. `callback_burn` emits the burn
+    /// event, or keeps the amount for `retry_burn` if the token refused it.
+    fn internal_burn(&self, amount: Balance) -> Promise {
+        return ext_ft_burn::ext(self.token_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(20))
+            .burn(U128(amount))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(10))
+                    .callback_burn(U128(amount))
+            );
+    }
+
+    /// Splits `amount` of settled revenue for `vapi_id` and credits every share except the
+    /// burn, which the caller sends to the token contract.
+    fn internal_settle_vapi(&mut self, vapi_id: &String, batch_id: &str, period_start: u64, period_end: u64, amount: Balance) -> SettlementRecord {
+        let mut vapi = self.internal_get_vapi(vapi_id);
+
+        let mut split = self.internal_fee_schedule(&vapi).split(amount);
+        if vapi.delegation_info.total_deposit_amount == 0 {
+            match self.zero_stake_policy {
+                ZeroStakePolicy::Coder => {
+                    split.coder_amount += split.delegator_amount;
+                    split.delegator_amount = 0;
+                }
+                ZeroStakePolicy::Treasury => {
+                    split.treasury_amount += split.delegator_amount;
+                    split.delegator_amount = 0;
+                }
+                ZeroStakePolicy::Undistributed => {}
+            }
+        }
+
+        vapi.coder_info.unclaimed_reward_amount += split.coder_amount;
+        internal_schedule_rewards(&mut vapi.delegation_info, split.delegator_amount);
+        self.internal_credit_treasury(&mut vapi, split.treasury_amount);
+
+        let record = SettlementRecord {
+            batch_id: batch_id.to_string(),
+            period_start,
+            period_end,
+            amount,
+            coder_amount: split.coder_amount,
+            delegator_amount: split.delegator_amount,
+            burn_amount: split.burn_amount,
+            treasury_amount: split.treasury_amount,
+            settled_at: env::block_timestamp(),
+        };
+        vapi.settlements.push(&record);
+        self.vapis.insert(vapi_id, &vapi);
+
+        return record;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig};
+
+    fn set_context(predecessor_id: AccountId, promise_results: Vec<PromiseResult>) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(5))
+                .predecessor_account_id(predecessor_id)
+                .build(),
+            near_sdk::test_vm_config(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            promise_results
+        );
+    }
+
+    fn new_contract() -> TicleCore {
+        set_context(accounts(0), vec![]);
+        return TicleCore::new("token.near".parse().unwrap(), accounts(0));
+    }
+
+    #[test]
+    fn failed_burns_are_kept_for_retry() {
+        let mut contract = new_contract();
+        set_context(accounts(5), vec![PromiseResult::Failed]);
+        contract.callback_burn(U128(7));
+        contract.callback_burn(U128(3));
+        assert_eq!(contract.get_pending_burn(), U128(10));
+        assert!(get_logs().iter().all(|log| !log.contains("\"event\":\"burn\"")));
+
+        set_context(accounts(0), vec![]);
+        contract.retry_burn();
+        assert_eq!(contract.get_pending_burn(), U128(0));
+
+        set_context(accounts(5), vec![PromiseResult::Successful(vec![])]);
+        contract.callback_burn(U128(10));
+        assert_eq!(contract.get_pending_burn(), U128(0));
+        assert!(get_logs().iter().any(|log| log.contains("\"event\":\"burn\"")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner can retry the burn")]
+    fn only_owner_retries_the_burn() {
+        let mut contract = new_contract();
+        set_context(accounts(5), vec![PromiseResult::Failed]);
+        contract.callback_burn(U128(7));
+
+        set_context(accounts(1), vec![]);
+        contract.retry_burn();
+    }
+}
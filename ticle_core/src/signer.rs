@@ -0,0 +1,255 @@
+use near_sdk::{CurveType, PublicKey};
+use ticle_signing::{decode_signature, verify_ed25519};
+
+use crate::*;
+
+/// Validity window of a key that may sign review requests, as block timestamps in nanoseconds.
+#[near(serializers = [borsh])]
+pub struct SignerKey {
+    valid_from: Option<u64>,
+    valid_until: Option<u64>,
+}
+
+impl SignerKey {
+    fn is_active(&self, timestamp: u64) -> bool {
+        return self.valid_from.is_none_or(|valid_from| timestamp >= valid_from)
+            && self.valid_until.is_none_or(|valid_until| timestamp < valid_until);
+    }
+}
+
+#[near(serializers = [json])]
+pub struct SignerKeyView {
+    public_key: PublicKey,
+    valid_from: Option<U64>,
+    valid_until: Option<U64>,
+    active: bool,
+}
+
+#[near]
+impl TicleCore {
+    pub fn add_signer_key(&mut self, public_key: PublicKey, valid_from: Option<U64>, valid_until: Option<U64>) {
+        self.assert_signer_admin();
+        require!(self.signer_keys.get(&public_key).is_none(), "Signer key already exists");
+        self.internal_add_signer_key(public_key, valid_from.map(|v| v.0), valid_until.map(|v| v.0));
+    }
+
+    pub fn revoke_signer_key(&mut self, public_key: PublicKey) {
+        self.assert_signer_admin();
+        self.signer_keys.remove(&public_key).expect("Signer key not found");
+        // 만료된 키는 서명할 수 없으므로 빼고, VAPI별로 더 높인 threshold도 채울 수 있어야 한다.
+        require!(self.active_signer_key_count() >= self.max_signer_threshold() as u64, "Lower the signer threshold before revoking this key");
+
+        EventLogVariant::SignerKeyRevoke(vec![SignerKeyLog {
+            public_key,
+            valid_from: None,
+            valid_until: None,
+        }]).emit();
+    }
+
+    /// Adds `new_public_key` and retires `old_public_key` at `old_key_valid_until`
+    /// (immediately when omitted), so signatures already handed out can still be
+    /// used during a grace period.
+    pub fn rotate_signer_key(&mut self, old_public_key: PublicKey, new_public_key: PublicKey, old_key_valid_until: Option<U64>) {
+        self.assert_signer_admin();
+        require!(self.signer_keys.get(&new_public_key).is_none(), "Signer key already exists");
+        let mut old_key = self.signer_keys.get(&old_public_key).expect("Signer key not found");
+
+        let valid_until = old_key_valid_until.map(|v| v.0).unwrap_or_else(env::block_timestamp);
+        old_key.valid_until = Some(old_key.valid_until.map_or(valid_until, |current| current.min(valid_until)));
+        self.signer_keys.insert(&old_public_key, &old_key);
+
+        EventLogVariant::SignerKeyRevoke(vec![SignerKeyLog {
+            public_key: old_public_key,
+            valid_from: old_key.valid_from.map(U64),
+            valid_until: old_key.valid_until.map(U64),
+        }]).emit();
+
+        self.internal_add_signer_key(new_public_key, None, None);
+    }
+
+    /// Number of distinct signer keys a review request needs, for every VAPI without its own threshold.
+    pub fn set_signer_threshold(&mut self, threshold: u8) {
+        self.assert_signer_admin();
+        self.assert_valid_threshold(threshold);
+        self.signer_threshold = threshold;
+        log!("[set_signer_threshold] {}", threshold);
+    }
+
+    /// Overrides the threshold for one VAPI, or falls back to the contract threshold
+    /// when `threshold` is `None`. A coder may only make their VAPI stricter.
+    pub fn set_vapi_signer_threshold(&mut self, vapi_id: String, threshold: Option<u8>) {
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        let account_id = env::predecessor_account_id();
+        if account_id != self.owner_id {
+            require!(vapi.coder_info.account_id == account_id, "Only owner or coder can set the VAPI signer threshold");
+            require!(threshold.unwrap_or(0) >= self.signer_threshold, "Coder can't lower the signer threshold");
+        }
+        if let Some(threshold) = threshold {
+            self.assert_valid_threshold(threshold);
+        }
+
+        if let Some(previous) = vapi.signer_threshold {
+            let count = self.vapi_signer_thresholds.get(&previous).copied().unwrap_or(0);
+            if count <= 1 {
+                self.vapi_signer_thresholds.remove(&previous);
+            } else {
+                self.vapi_signer_thresholds.insert(previous, count - 1);
+            }
+        }
+        if let Some(threshold) = threshold {
+            *self.vapi_signer_thresholds.entry(threshold).or_insert(0) += 1;
+        }
+
+        vapi.signer_threshold = threshold;
+        self.vapis.insert(&vapi_id, &vapi);
+        log!("[set_vapi_signer_threshold] {}: {:?}", vapi_id, threshold);
+    }
+
+    pub fn get_signer_threshold(&self, vapi_id: Option<String>) -> u8 {
+        return vapi_id
+            .and_then(|vapi_id| self.internal_get_vapi(&vapi_id).signer_threshold)
+            .unwrap_or(self.signer_threshold);
+    }
+
+    pub fn get_signer_keys(&self) -> Vec<SignerKeyView> {
+        let now = env::block_timestamp();
+        return self.signer_keys
+            .iter()
+            .map(|(public_key, signer_key)| SignerKeyView {
+                public_key,
+                valid_from: signer_key.valid_from.map(U64),
+                valid_until: signer_key.valid_until.map(U64),
+                active: signer_key.is_active(now),
+            })
+            .collect();
+    }
+}
+
+impl TicleCore {
+    fn assert_signer_admin(&self) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can manage signer keys");
+    }
+
+    fn assert_valid_threshold(&self, threshold: u8) {
+        require!(threshold > 0, "Signer threshold must be at least 1");
+        require!(threshold as u64 <= self.active_signer_key_count(), "Signer threshold can't exceed the number of signer keys");
+    }
+
+    fn active_signer_key_count(&self) -> u64 {
+        let now = env::block_timestamp();
+        return self.signer_keys.values().filter(|signer_key| signer_key.is_active(now)).count() as u64;
+    }
+
+    /// Highest threshold any review request may need: the contract threshold or a stricter VAPI one.
+    fn max_signer_threshold(&self) -> u8 {
+        let vapi_max = self.vapi_signer_thresholds.keys().next_back().copied().unwrap_or(0);
+        return std::cmp::max(self.signer_threshold, vapi_max);
+    }
+
+    /// Requires at least `threshold` of `signatures` to come from distinct active signer keys.
+    pub(crate) fn assert_signatures(&self, message: &[u8], signatures: Vec<String>, threshold: u8) {
+        require!(!signatures.is_empty(), "Invalid signature");
+
+        let mut signer_keys: Vec<PublicKey> = Vec::new();
+        for signature in signatures {
+            let signer_key = self.verify_signature(message, signature).unwrap_or_else(|| env::panic_str("Invalid signature"));
+            require!(!signer_keys.contains(&signer_key), "Duplicate signer key");
+            signer_keys.push(signer_key);
+        }
+
+        require!(
+            signer_keys.len() >= threshold as usize,
+            format!("Not enough signatures: {} of {} required", signer_keys.len(), threshold)
+        );
+    }
+
+    pub(crate) fn internal_add_signer_key(&mut self, public_key: PublicKey, valid_from: Option<u64>, valid_until: Option<u64>) {
+        require!(public_key.curve_type() == CurveType::ED25519, "Only ed25519 signer keys are supported");
+        if let (Some(valid_from), Some(valid_until)) = (valid_from, valid_until) {
+            require!(valid_from < valid_until, "valid_from must be earlier than valid_until");
+        }
+
+        self.signer_keys.insert(&public_key, &SignerKey { valid_from, valid_until });
+
+        EventLogVariant::SignerKeyAdd(vec![SignerKeyLog {
+            public_key,
+            valid_from: valid_from.map(U64),
+            valid_until: valid_until.map(U64),
+        }]).emit();
+    }
+
+    /// Checks `signature` (`ed25519:<base58>`) against every signer key that is active
+    /// at the current block, and returns the key that produced it.
+    pub(crate) fn verify_signature(&self, message: &[u8], signature: String) -> Option<PublicKey> {
+        let signature = decode_signature(&signature)?;
+
+        let now = env::block_timestamp();
+        for (public_key, signer_key) in self.signer_keys.iter() {
+            if !signer_key.is_active(now) {
+                continue;
+            }
+
+            if verify_ed25519(&signature, message, &public_key) {
+                log!("[verify_signature] signed by {}", String::from(&public_key));
+                return Some(public_key);
+            }
+        }
+
+        log!("[verify_signature] no active signer key matched");
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const VAPI_ID: &str = "vapi";
+    const NOW: u64 = 1_000;
+
+    fn signer_key(seed: u8) -> PublicKey {
+        return PublicKey::from_parts(CurveType::ED25519, vec![seed; 32]).unwrap();
+    }
+
+    /// A contract owned by `accounts(0)` with the deployer key, `signer_key(1)` and a VAPI of `accounts(1)`.
+    fn contract_with_two_keys() -> TicleCore {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(5))
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(NOW)
+            .build());
+        let mut contract = TicleCore::new("token.near".parse().unwrap(), accounts(0));
+        contract.add_signer_key(signer_key(1), None, None);
+        contract.vapis.insert(&VAPI_ID.to_string(), &VAPI::new(VAPI_ID, accounts(1)));
+        return contract;
+    }
+
+    #[test]
+    #[should_panic(expected = "Lower the signer threshold before revoking this key")]
+    fn revoke_keeps_enough_keys_for_a_stricter_vapi() {
+        let mut contract = contract_with_two_keys();
+        contract.set_vapi_signer_threshold(VAPI_ID.to_string(), Some(2));
+        contract.revoke_signer_key(signer_key(1));
+    }
+
+    #[test]
+    fn revoke_after_the_vapi_threshold_is_cleared() {
+        let mut contract = contract_with_two_keys();
+        contract.set_vapi_signer_threshold(VAPI_ID.to_string(), Some(2));
+        contract.set_vapi_signer_threshold(VAPI_ID.to_string(), None);
+        contract.revoke_signer_key(signer_key(1));
+        assert_eq!(contract.get_signer_keys().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lower the signer threshold before revoking this key")]
+    fn expired_keys_do_not_count() {
+        let mut contract = contract_with_two_keys();
+        contract.add_signer_key(signer_key(2), None, Some(U64(NOW - 1)));
+        contract.set_signer_threshold(2);
+        // 남는 키는 2개지만 signer_key(2)는 이미 만료되었다.
+        contract.revoke_signer_key(signer_key(1));
+    }
+}
@@ -0,0 +1,216 @@
+use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_sdk::{assert_one_yocto, StorageUsage};
+
+use crate::*;
+
+/// Bytes taken by an account's own entry in `storage_accounts`, including the
+/// 40 bytes of per-record overhead charged by the runtime.
+pub const ACCOUNT_STORAGE_BYTES: StorageUsage = 150;
+
+/// Upper bound of what `create_vapi` writes (the VAPI itself plus the coder index),
+/// checked by `test_gas_simulation`.
+pub const VAPI_STORAGE_BYTES: StorageUsage = 900;
+
+/// Upper bound of what a first deposit into a VAPI writes for the delegator.
+pub const DELEGATOR_STORAGE_BYTES: StorageUsage = 200;
+
+/// Upper bound of what a `withdraw` writes for the delegator's unbond entry and, on the
+/// first unbond in a VAPI, the account's index of VAPIs with unbonds.
+pub const UNBOND_STORAGE_BYTES: StorageUsage = 600;
+
+/// Upper bound of what one reviewer entry of a review request writes.
+pub const REVIEWER_STORAGE_BYTES: StorageUsage = 300;
+
+/// Most state one account may own, its own entry included: about a hundred VAPIs, or
+/// any mix of VAPIs, open reviews, deposits and unbonds of the same size. Deposits
+/// above it are refunded.
+pub const MAX_ACCOUNT_STORAGE_BYTES: StorageUsage = 100_000;
+
+#[near(serializers = [borsh])]
+pub struct AccountStorage {
+    deposit: NearToken,
+    used_bytes: StorageUsage,
+}
+
+impl AccountStorage {
+    fn locked(&self) -> NearToken {
+        return env::storage_byte_cost().saturating_mul((ACCOUNT_STORAGE_BYTES + self.used_bytes) as u128);
+    }
+
+    fn available(&self) -> NearToken {
+        return self.deposit.saturating_sub(self.locked());
+    }
+
+    fn to_storage_balance(&self) -> StorageBalance {
+        return StorageBalance {
+            total: self.deposit,
+            available: self.available(),
+        };
+    }
+}
+
+#[near]
+impl StorageManagement for TicleCore {
+    #[payable]
+    fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let registration_only = registration_only.unwrap_or(false);
+        let bounds = self.storage_balance_bounds();
+        let max_balance = bounds.max.unwrap();
+
+        let (account_storage, refund) = match self.storage_accounts.get(&account_id) {
+            Some(mut account_storage) => {
+                if registration_only {
+                    log!("[storage_deposit] {} is already registered", account_id);
+                    (account_storage, amount)
+                } else {
+                    let accepted = std::cmp::min(amount, max_balance.saturating_sub(account_storage.deposit));
+                    account_storage.deposit = account_storage.deposit.saturating_add(accepted);
+                    (account_storage, amount.saturating_sub(accepted))
+                }
+            }
+            None => {
+                require!(amount >= bounds.min, "The attached deposit is less than the minimum storage balance");
+                let deposit = if registration_only { bounds.min } else { std::cmp::min(amount, max_balance) };
+                (AccountStorage { deposit, used_bytes: 0 }, amount.saturating_sub(deposit))
+            }
+        };
+        if refund.as_yoctonear() > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        self.storage_accounts.insert(&account_id, &account_storage);
+        return account_storage.to_storage_balance();
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<NearToken>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account_storage = self.storage_accounts.get(&account_id).expect("Account is not registered");
+
+        let available = account_storage.available();
+        let amount = amount.unwrap_or(available);
+        require!(amount <= available, "The amount is greater than the available storage balance");
+
+        account_storage.deposit = account_storage.deposit.saturating_sub(amount);
+        self.storage_accounts.insert(&account_id, &account_storage);
+        if amount.as_yoctonear() > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+
+        return account_storage.to_storage_balance();
+    }
+
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        require!(!force.unwrap_or(false), "Force unregister is not supported");
+
+        let account_id = env::predecessor_account_id();
+        let account_storage = match self.storage_accounts.get(&account_id) {
+            Some(account_storage) => account_storage,
+            None => {
+                log!("[storage_unregister] {} is not registered", account_id);
+                return false;
+            }
+        };
+        require!(account_storage.used_bytes == 0, "Can't unregister an account that still owns contract state");
+
+        self.storage_accounts.remove(&account_id);
+        Promise::new(account_id).transfer(account_storage.deposit);
+        return true;
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        return StorageBalanceBounds {
+            min: env::storage_byte_cost().saturating_mul(ACCOUNT_STORAGE_BYTES as u128),
+            max: Some(env::storage_byte_cost().saturating_mul(MAX_ACCOUNT_STORAGE_BYTES as u128)),
+        };
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        return self.storage_accounts.get(&account_id).map(|account_storage| account_storage.to_storage_balance());
+    }
+}
+
+impl TicleCore {
+    /// Charges `account_id` for whatever the current call wrote since `initial_storage_usage`,
+    /// or gives the bytes back when the call freed state.
+    pub(crate) fn internal_update_storage(&mut self, account_id: &AccountId, initial_storage_usage: StorageUsage) {
+        let current_storage_usage = env::storage_usage();
+        if current_storage_usage <= initial_storage_usage {
+            // 마이그레이션된 항목은 아무도 비용을 내지 않았으므로 미등록 계정이어도 그냥 넘어간다.
+            if let Some(mut account_storage) = self.storage_accounts.get(account_id) {
+                account_storage.used_bytes = account_storage.used_bytes.saturating_sub(initial_storage_usage - current_storage_usage);
+                self.storage_accounts.insert(account_id, &account_storage);
+            }
+            return;
+        }
+
+        let mut account_storage = self.storage_accounts.get(account_id).expect("Account is not registered for storage");
+        account_storage.used_bytes += current_storage_usage - initial_storage_usage;
+        require!(ACCOUNT_STORAGE_BYTES + account_storage.used_bytes <= MAX_ACCOUNT_STORAGE_BYTES, "Account storage limit reached");
+        require!(
+            account_storage.deposit >= account_storage.locked(),
+            format!("Not enough storage deposit, {} bytes are required", ACCOUNT_STORAGE_BYTES + account_storage.used_bytes)
+        );
+        self.storage_accounts.insert(account_id, &account_storage);
+    }
+
+    /// Fails early when `account_id` can't pay for `bytes` more, for writes that only
+    /// happen later in a callback where a panic can no longer refund the tokens.
+    pub(crate) fn assert_storage_available(&self, account_id: &AccountId, bytes: StorageUsage) {
+        let account_storage = self.storage_accounts.get(account_id).expect("Account is not registered for storage");
+        let required = env::storage_byte_cost().saturating_mul(bytes as u128);
+        require!(account_storage.available() >= required, format!("Not enough storage deposit, {} more bytes are required", bytes));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn set_caller(account_id: AccountId, deposit: NearToken) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(5))
+            .predecessor_account_id(account_id)
+            .attached_deposit(deposit)
+            .build());
+    }
+
+    fn bytes_cost(bytes: StorageUsage) -> NearToken {
+        return env::storage_byte_cost().saturating_mul(bytes as u128);
+    }
+
+    fn new_contract() -> TicleCore {
+        set_caller(accounts(0), NearToken::from_yoctonear(0));
+        return TicleCore::new("token.near".parse().unwrap(), accounts(0));
+    }
+
+    #[test]
+    fn deposit_above_max_is_refunded() {
+        let mut contract = new_contract();
+        set_caller(accounts(1), bytes_cost(MAX_ACCOUNT_STORAGE_BYTES + 1_000));
+        let balance = contract.storage_deposit(None, None);
+        assert_eq!(balance.total, bytes_cost(MAX_ACCOUNT_STORAGE_BYTES));
+
+        set_caller(accounts(1), bytes_cost(1_000));
+        let balance = contract.storage_deposit(None, None);
+        assert_eq!(balance.total, contract.storage_balance_bounds().max.unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough storage deposit, 900 more bytes are required")]
+    fn create_vapi_needs_vapi_storage_bytes() {
+        let mut contract = new_contract();
+        // 실제로 쓰는 양보다는 많지만 VAPI_STORAGE_BYTES에는 못 미치는 예치금
+        set_caller(accounts(1), bytes_cost(ACCOUNT_STORAGE_BYTES + VAPI_STORAGE_BYTES - 1));
+        contract.storage_deposit(None, None);
+        contract.create_vapi("vapi".to_string());
+    }
+}
+
@@ -0,0 +1,83 @@
+use crate::*;
+
+#[near(serializers = [json])]
+pub struct TreasuryView {
+    treasury_id: Option<AccountId>,
+    /// Collected and not withdrawn yet.
+    balance: U128,
+    /// Everything ever credited to the treasury.
+    total_collected: U128,
+}
+
+#[near]
+impl TicleCore {
+    pub fn set_treasury(&mut self, treasury_id: AccountId) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can set the treasury");
+        log!("[set_treasury] {:?} -> {}", self.treasury_id, treasury_id);
+        let old_treasury_id = self.treasury_id.replace(treasury_id.clone());
+
+        EventLogVariant::TreasuryUpdate(vec![TreasuryUpdateLog {
+            old_treasury_id,
+            new_treasury_id: treasury_id,
+        }]).emit();
+    }
+
+    /// Sends treasury funds to the treasury account. Withdraws the whole balance when
+    /// `amount` is omitted.
+    pub fn withdraw_treasury(&mut self, amount: Option<U128>) -> Promise {
+        let treasury_id = env::predecessor_account_id();
+        require!(self.treasury_id.as_ref() == Some(&treasury_id), "Only treasury can withdraw");
+
+        let amount: Balance = amount.map(|amount| amount.0).unwrap_or(self.treasury_balance);
+        require!(amount > 0, "Nothing to withdraw");
+        require!(amount <= self.treasury_balance, "Not enough treasury balance");
+        self.treasury_balance -= amount;
+
+        return ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(20))
+            .ft_transfer(treasury_id.clone(), U128(amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(20))
+                    .callback_withdraw_treasury(treasury_id, U128(amount))
+            );
+    }
+
+    #[private]
+    pub fn callback_withdraw_treasury(&mut self, treasury_id: AccountId, amount: U128) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                EventLogVariant::TreasuryWithdraw(vec![TreasuryWithdrawLog {
+                    treasury_id,
+                    amount,
+                }]).emit();
+            }
+            _ => {
+                log!("[callback_withdraw_treasury] ft_transfer failed, restoring {}", amount.0);
+                self.treasury_balance += amount.0;
+            }
+        }
+    }
+
+    pub fn get_treasury(&self) -> TreasuryView {
+        return TreasuryView {
+            treasury_id: self.treasury_id.clone(),
+            balance: U128(self.treasury_balance),
+            total_collected: U128(self.treasury_total_collected),
+        };
+    }
+
+    /// Everything settlements of `vapi_id` have credited to the treasury.
+    pub fn get_vapi_treasury_amount(&self, vapi_id: String) -> U128 {
+        return U128(self.internal_get_vapi(&vapi_id).treasury_amount);
+    }
+}
+
+impl TicleCore {
+    pub(crate) fn internal_credit_treasury(&mut self, vapi: &mut VAPI, amount: Balance) {
+        vapi.treasury_amount += amount;
+        self.treasury_balance += amount;
+        self.treasury_total_collected += amount;
+    }
+}
@@ -0,0 +1,315 @@
+use crate::*;
+
+const ONE_DAY: u64 = 60 * 60 * 24 * 1_000_000_000;
+
+/// Unbonding period of a newly created VAPI.
+pub const DEFAULT_UNBONDING_PERIOD: u64 = 7 * ONE_DAY;
+
+/// Longest unbonding period the owner may configure.
+pub const MAX_UNBONDING_PERIOD: u64 = 30 * ONE_DAY;
+
+/// Principal that left the stake with `withdraw` and can be paid out with
+/// `complete_unbond` once the block timestamp reaches `unlock_at`.
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub struct Unbond {
+    amount: Balance,
+    unlock_at: u64,
+}
+
+#[near(serializers = [json])]
+pub struct UnbondView {
+    vapi_id: String,
+    amount: U128,
+    unlock_at: U64,
+    unlocked: bool,
+}
+
+#[near]
+impl TicleCore {
+    /// Sets how long (in nanoseconds) withdrawn principal stays locked for `vapi_id`.
+    /// Only applies to withdrawals made afterwards.
+    pub fn set_unbonding_period(&mut self, vapi_id: String, unbonding_period: U64) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can set the unbonding period");
+        require!(unbonding_period.0 <= MAX_UNBONDING_PERIOD, "Unbonding period is too long");
+
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        vapi.delegation_info.unbonding_period = unbonding_period.0;
+        self.vapis.insert(&vapi_id, &vapi);
+        log!("[set_unbonding_period] {}: {}", vapi_id, unbonding_period.0);
+    }
+
+    /// Pays out every unbond of the caller in `vapi_id` whose period has passed.
+    pub fn complete_unbond(&mut self, vapi_id: String) -> Promise {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let mut vapi = self.internal_get_vapi(&vapi_id);
+        let unbonds = vapi.delegation_info.unbonds.get(&account_id).unwrap_or_default();
+
+        let now = env::block_timestamp();
+        let (unlocked, locked): (Vec<Unbond>, Vec<Unbond>) = unbonds.into_iter().partition(|unbond| unbond.unlock_at <= now);
+        let amount: Balance = unlocked.iter().map(|unbond| unbond.amount).sum();
+        require!(amount > 0, "No unlocked unbonds");
+
+        if locked.is_empty() {
+            vapi.delegation_info.unbonds.remove(&account_id);
+            self.internal_remove_unbond_vapi(&account_id, &vapi_id);
+        } else {
+            vapi.delegation_info.unbonds.insert(&account_id, &locked);
+        }
+        self.vapis.insert(&vapi_id, &vapi);
+        self.internal_update_storage(&account_id, initial_storage_usage);
+
+        return ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(20))
+            .ft_transfer(account_id.clone(), U128(amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(20))
+                    .callback_complete_unbond(account_id, vapi_id, U128(amount))
+            );
+    }
+
+    #[private]
+    pub fn callback_complete_unbond(&mut self, account_id: AccountId, vapi_id: String, amount: U128) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                EventLogVariant::UnbondComplete(vec![UnbondCompleteLog {
+                    vapi_id,
+                    account_id,
+                    amount,
+                }]).emit();
+            }
+            _ => {
+                // ft_transfer가 실패하면 바로 수령 가능한 unbond로 되돌린다.
+                log!("[callback_complete_unbond] ft_transfer failed, restoring {}", amount.0);
+                self.internal_restore_unlocked(&account_id, &vapi_id, amount.0);
+            }
+        }
+    }
+
+    #[private]
+    pub fn callback_withdraw_reward(&mut self, account_id: AccountId, vapi_id: String, amount: U128) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                EventLogVariant::RewardClaim(vec![RewardClaimLog {
+                    vapi_id,
+                    account_id,
+                    amount,
+                }]).emit();
+            }
+            _ => {
+                // delegator 항목이 이미 지워졌을 수 있으므로 바로 수령 가능한 unbond로 남긴다.
+                log!("[callback_withdraw_reward] ft_transfer failed, restoring {}", amount.0);
+                self.internal_restore_unlocked(&account_id, &vapi_id, amount.0);
+            }
+        }
+    }
+
+    pub fn get_unbonding_period(&self, vapi_id: String) -> U64 {
+        return U64(self.internal_get_vapi(&vapi_id).delegation_info.unbonding_period);
+    }
+
+    /// Pending unbonds of `account_id`, oldest first within each VAPI. Lists every VAPI
+    /// the account has unbonds in when `vapi_id` is omitted.
+    pub fn get_unbonds(&self, account_id: AccountId, vapi_id: Option<String>) -> Vec<UnbondView> {
+        let vapi_ids: Vec<String> = match vapi_id {
+            Some(vapi_id) => vec![vapi_id],
+            None => self.unbond_vapis_per_account.get(&account_id).map(|vapi_ids| vapi_ids.to_vec()).unwrap_or_default(),
+        };
+
+        let now = env::block_timestamp();
+        return vapi_ids
+            .into_iter()
+            .flat_map(|vapi_id| {
+                let unbonds = self.internal_get_vapi(&vapi_id).delegation_info.unbonds.get(&account_id).unwrap_or_default();
+                unbonds.into_iter().map(move |unbond| UnbondView {
+                    vapi_id: vapi_id.clone(),
+                    amount: U128(unbond.amount),
+                    unlock_at: U64(unbond.unlock_at),
+                    unlocked: unbond.unlock_at <= now,
+                })
+            })
+            .collect();
+    }
+}
+
+impl TicleCore {
+    /// Moves `amount` of the delegator's stake into a new unbond entry. The amount stops
+    /// earning rewards right away. Returns when it unlocks and the rewards released since
+    /// the delegator last claimed, which the caller pays out.
+    pub(crate) fn internal_unbond(&mut self, vapi: &mut VAPI, vapi_id: &String, account_id: &AccountId, amount: Balance) -> (u64, Balance) {
+        let delegation_info = &mut vapi.delegation_info;
+        internal_release_rewards(delegation_info);
+        let mut delegator_info = delegation_info.delegator_infos.get(account_id).expect("Delegator not found");
+        require!(amount <= delegator_info.deposit_amount, "Not enough deposit");
+
+        // withdraw에서 보상을 수령한 뒤 callback까지 풀린 보상
+        let reward_amount = internal_pending_reward(&delegator_info, delegation_info.acc_reward_per_share);
+        delegator_info.deposit_amount -= amount;
+        if delegator_info.deposit_amount == 0 {
+            delegation_info.delegator_infos.remove(account_id);
+        } else {
+            delegator_info.reward_debt = delegator_info.deposit_amount * delegation_info.acc_reward_per_share / ACC_REWARD_PRECISION;
+            delegation_info.delegator_infos.insert(account_id, &delegator_info);
+        }
+        delegation_info.total_deposit_amount -= amount;
+
+        let unlock_at = env::block_timestamp() + delegation_info.unbonding_period;
+        let mut unbonds = delegation_info.unbonds.get(account_id).unwrap_or_default();
+        unbonds.push(Unbond { amount, unlock_at });
+        delegation_info.unbonds.insert(account_id, &unbonds);
+        self.internal_add_unbond_vapi(account_id, vapi_id);
+
+        return (unlock_at, reward_amount);
+    }
+
+    /// Adds `amount` as an unbond of `account_id` that can be completed right away.
+    fn internal_restore_unlocked(&mut self, account_id: &AccountId, vapi_id: &String, amount: Balance) {
+        let initial_storage_usage = env::storage_usage();
+        let mut vapi = self.vapis.get(vapi_id).expect("VAPI not found");
+        let mut unbonds = vapi.delegation_info.unbonds.get(account_id).unwrap_or_default();
+        unbonds.push(Unbond {
+            amount,
+            unlock_at: env::block_timestamp(),
+        });
+        vapi.delegation_info.unbonds.insert(account_id, &unbonds);
+        self.vapis.insert(vapi_id, &vapi);
+        self.internal_add_unbond_vapi(account_id, vapi_id);
+        self.internal_update_storage(account_id, initial_storage_usage);
+    }
+
+    fn internal_add_unbond_vapi(&mut self, account_id: &AccountId, vapi_id: &String) {
+        let mut vapi_ids = self.unbond_vapis_per_account.get(account_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::AccountUnbondVapis { account_hash: env::sha256_array(account_id.as_str().as_bytes()) })
+        });
+        vapi_ids.insert(vapi_id);
+        self.unbond_vapis_per_account.insert(account_id, &vapi_ids);
+    }
+
+    fn internal_remove_unbond_vapi(&mut self, account_id: &AccountId, vapi_id: &String) {
+        if let Some(mut vapi_ids) = self.unbond_vapis_per_account.get(account_id) {
+            vapi_ids.remove(vapi_id);
+            if vapi_ids.is_empty() {
+                self.unbond_vapis_per_account.remove(account_id);
+            } else {
+                self.unbond_vapis_per_account.insert(account_id, &vapi_ids);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const VAPI_A: &str = "vapi-a";
+    const VAPI_B: &str = "vapi-b";
+
+    fn set_context(predecessor_id: AccountId, deposit: NearToken, promise_results: Vec<PromiseResult>) {
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(5))
+                .predecessor_account_id(predecessor_id)
+                .attached_deposit(deposit)
+                .build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            promise_results
+        );
+    }
+
+    /// `accounts(2)` staked 100 in `vapi-a` and `vapi-b` of `accounts(1)`, and each
+    /// stake has earned 100 of rewards that were not claimed yet.
+    fn contract_with_rewards() -> TicleCore {
+        set_context(accounts(0), NearToken::from_yoctonear(0), vec![]);
+        let mut contract = TicleCore::new("token.near".parse().unwrap(), accounts(0));
+        for account_id in [accounts(1), accounts(2)] {
+            set_context(account_id, NearToken::from_near(1), vec![]);
+            contract.storage_deposit(None, None);
+        }
+
+        for vapi_id in [VAPI_A, VAPI_B] {
+            set_context(accounts(1), NearToken::from_yoctonear(0), vec![]);
+            contract.create_vapi(vapi_id.to_string());
+            set_context(accounts(5), NearToken::from_yoctonear(0), vec![]);
+            contract.callback_internal_deposit(accounts(2), vapi_id.to_string(), 100);
+
+            let mut vapi = contract.internal_get_vapi(&vapi_id.to_string());
+            vapi.delegation_info.acc_reward_per_share = ACC_REWARD_PRECISION;
+            contract.vapis.insert(&vapi_id.to_string(), &vapi);
+        }
+        return contract;
+    }
+
+    /// Amounts of the `ft_transfer` calls created so far.
+    fn ft_transfer_amounts() -> Vec<U128> {
+        return near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                MockAction::FunctionCallWeight { method_name, args, .. } if method_name == b"ft_transfer" => {
+                    let args: near_sdk::serde_json::Value = near_sdk::serde_json::from_slice(&args).unwrap();
+                    Some(near_sdk::serde_json::from_value(args["amount"].clone()).unwrap())
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    #[test]
+    fn released_rewards_are_paid_instead_of_locked() {
+        let mut contract = contract_with_rewards();
+        set_context(accounts(5), NearToken::from_yoctonear(0), vec![]);
+        let initial_storage_usage = env::storage_usage();
+        contract.callback_withdraw(&accounts(2), VAPI_A.to_string(), U128(40));
+        let storage_used = env::storage_usage() - initial_storage_usage;
+        assert!(storage_used <= UNBOND_STORAGE_BYTES, "withdraw used {} bytes", storage_used);
+
+        let unbonds = contract.get_unbonds(accounts(2), Some(VAPI_A.to_string()));
+        assert_eq!(unbonds.len(), 1);
+        assert_eq!(unbonds[0].amount, U128(40));
+        assert_eq!(ft_transfer_amounts(), vec![U128(100)]);
+    }
+
+    #[test]
+    fn failed_reward_transfer_leaves_an_unlocked_unbond() {
+        let mut contract = contract_with_rewards();
+        set_context(accounts(5), NearToken::from_yoctonear(0), vec![]);
+        contract.callback_withdraw(&accounts(2), VAPI_A.to_string(), U128(100));
+
+        set_context(accounts(5), NearToken::from_yoctonear(0), vec![PromiseResult::Failed]);
+        contract.callback_withdraw_reward(accounts(2), VAPI_A.to_string(), U128(100));
+        let unbonds = contract.get_unbonds(accounts(2), Some(VAPI_A.to_string()));
+        assert_eq!(unbonds.len(), 2);
+        assert_eq!(unbonds[1].amount, U128(100));
+        assert!(unbonds[1].unlocked);
+        assert!(!unbonds[0].unlocked);
+    }
+
+    #[test]
+    fn unbonds_are_listed_across_vapis() {
+        let mut contract = contract_with_rewards();
+        set_context(accounts(5), NearToken::from_yoctonear(0), vec![]);
+        contract.callback_withdraw(&accounts(2), VAPI_A.to_string(), U128(10));
+        contract.callback_withdraw(&accounts(2), VAPI_B.to_string(), U128(20));
+
+        let unbonds = contract.get_unbonds(accounts(2), None);
+        let amounts: Vec<(String, U128)> = unbonds.into_iter().map(|unbond| (unbond.vapi_id, unbond.amount)).collect();
+        assert_eq!(amounts, vec![(VAPI_A.to_string(), U128(10)), (VAPI_B.to_string(), U128(20))]);
+
+        // 기간이 0인 unbond를 모두 수령하면 목록에서 빠진다.
+        let mut vapi = contract.internal_get_vapi(&VAPI_A.to_string());
+        vapi.delegation_info.unbonds.insert(&accounts(2), &vec![Unbond { amount: 10, unlock_at: 0 }]);
+        contract.vapis.insert(&VAPI_A.to_string(), &vapi);
+        set_context(accounts(2), NearToken::from_yoctonear(0), vec![]);
+        contract.complete_unbond(VAPI_A.to_string());
+        assert_eq!(contract.get_unbonds(accounts(2), None).len(), 1);
+    }
+}
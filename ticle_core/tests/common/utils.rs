@@ -17,6 +17,19 @@ pub async fn register_user(contract: &Contract, account_id: &AccountId) -> anyho
     return Ok(());
 }
 
+pub async fn deposit_storage(contract: &Contract, account: &Account, amount: NearToken) -> anyhow::Result<()> {
+    let res = account
+        .call(contract.id(), "storage_deposit")
+        .args_json((Option::<AccountId>::None, Option::<bool>::None))
+        .max_gas()
+        .deposit(amount)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    return Ok(());
+}
+
 pub async fn create_users(worker: &Worker<impl DevNetwork>, users: Vec<&str>, nears: Vec<u128>) -> anyhow::Result<Vec<Account>> {
     let mut accounts = Vec::new();
     let account = worker.dev_create_account().await?;
@@ -34,6 +47,16 @@ pub async fn create_users(worker: &Worker<impl DevNetwork>, users: Vec<&str>, ne
 pub async fn init(
     worker: &Worker<impl DevNetwork>,
     initial_balance: U128
+) -> anyhow::Result<(Contract, Account, Contract)> {
+    let core_wasm = include_bytes!("../../../target/wasm32-unknown-unknown/release/ticle_core.wasm");
+    return init_with_core_wasm(worker, initial_balance, core_wasm).await;
+}
+
+/// `init` with another build of TicleCore, e.g. the `dalek` baseline of the gas simulation.
+pub async fn init_with_core_wasm(
+    worker: &Worker<impl DevNetwork>,
+    initial_balance: U128,
+    core_wasm: &[u8]
 ) -> anyhow::Result<(Contract, Account, Contract)> {
     let token_wasm = include_bytes!("../../../target/wasm32-unknown-unknown/release/token.wasm");
     let ft_contract = worker.dev_deploy(token_wasm).await?;
@@ -50,7 +73,7 @@ pub async fn init(
 
     let res = ft_contract
         .call("new")
-        .args_json((ft_contract.id(), initial_balance, token_metadata))
+        .args_json((ft_contract.id(), initial_balance, token_metadata, U128(initial_balance.0 * 2)))
         .max_gas()
         .transact()
         .await?;
@@ -70,7 +93,6 @@ pub async fn init(
         .await?;
     assert!(res.is_success());
 
-    let core_wasm = include_bytes!("../../../target/wasm32-unknown-unknown/release/ticle_core.wasm");
     let core_contract = worker.dev_deploy(core_wasm).await?;
 
     let res = owner
@@ -1,22 +1,79 @@
 use std::str::FromStr;
 
-use near_sdk::{json_types::U128, NearToken};
+use near_sdk::{json_types::{U128, U64}, NearToken};
 use serde_json::json;
 use near_crypto::SecretKey;
+use near_contract_standards::storage_management::StorageBalanceBounds;
+use near_workspaces::{types::Gas, Contract, DevNetwork, Worker};
+use ticle_core::payload::ReviewRequestPayload;
+use ticle_core::storage::{ACCOUNT_STORAGE_BYTES, MAX_ACCOUNT_STORAGE_BYTES, REVIEWER_STORAGE_BYTES, VAPI_STORAGE_BYTES};
 
 use crate::common::utils::*;
 pub mod common;
 
+// near-cli가 함수 호출에 기본으로 붙이는 가스
+const MAX_CREATE_VAPI_TGAS: u64 = 30;
+// 지갑이 ft_transfer_call에 붙이는 가스. 토큰 컨트랙트가 전송과 resolve에 30 TGas를 남겨두므로
+// TicleCore의 ft_on_transfer는 나머지 안에서 끝나야 한다.
+const MAX_REQUEST_REVIEW_TGAS: u64 = 100;
+const MAX_FT_ON_TRANSFER_TGAS: u64 = MAX_REQUEST_REVIEW_TGAS - 30;
+
+const CORE_WASM: &[u8] = include_bytes!("../../target/wasm32-unknown-unknown/release/ticle_core.wasm");
+// 비교 기준: 서명을 ed25519-dalek으로 검증하던 때의 빌드 (README의 `dalek` 빌드)
+const DALEK_CORE_WASM: &[u8] = include_bytes!("../../target/dalek/wasm32-unknown-unknown/release/ticle_core.wasm");
+
+/// What one build of TicleCore spent in the simulation.
+struct Simulation {
+    core_contract: Contract,
+    wasm_bytes: usize,
+    create_vapi_gas: Gas,
+    ft_on_transfer_gas: Gas,
+    request_review_gas: Gas,
+}
+
 #[tokio::test]
 async fn test_gas_simulation() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let current = simulate(&worker, CORE_WASM).await?;
+    let dalek = simulate(&worker, DALEK_CORE_WASM).await?;
+
+    println!(
+        "[ticle_core] wasm size: {} bytes, {} bytes with ed25519-dalek ({} bytes saved)",
+        current.wasm_bytes, dalek.wasm_bytes, dalek.wasm_bytes as i64 - current.wasm_bytes as i64
+    );
+    println!(
+        "[request_review] ft_on_transfer gas: {}, {} with ed25519-dalek ({} gas saved)",
+        current.ft_on_transfer_gas, dalek.ft_on_transfer_gas, dalek.ft_on_transfer_gas.as_gas() as i64 - current.ft_on_transfer_gas.as_gas() as i64
+    );
+    // 서명 검증을 ed25519_verify 호스트 함수로 옮겨서 코드와 가스가 모두 줄어야 한다.
+    assert!(current.wasm_bytes < dalek.wasm_bytes);
+    assert!(current.ft_on_transfer_gas < dalek.ft_on_transfer_gas);
+
+    assert!(current.create_vapi_gas <= Gas::from_tgas(MAX_CREATE_VAPI_TGAS), "create_vapi burnt {}", current.create_vapi_gas);
+    assert!(current.ft_on_transfer_gas <= Gas::from_tgas(MAX_FT_ON_TRANSFER_TGAS), "ft_on_transfer burnt {}", current.ft_on_transfer_gas);
+    assert!(current.request_review_gas <= Gas::from_tgas(MAX_REQUEST_REVIEW_TGAS), "request_review burnt {}", current.request_review_gas);
+
+    // storage_balance_bounds의 min은 계정 레코드 크기로 계산된다.
+    let bounds = current.core_contract
+        .call("storage_balance_bounds")
+        .view()
+        .await?
+        .json::<StorageBalanceBounds>()?;
+    assert_eq!(bounds.min, near_sdk::env::storage_byte_cost().saturating_mul(ACCOUNT_STORAGE_BYTES as u128));
+    assert_eq!(bounds.max, Some(near_sdk::env::storage_byte_cost().saturating_mul(MAX_ACCOUNT_STORAGE_BYTES as u128)));
+
+    return Ok(());
+}
+
+/// Creates a VAPI and requests two reviews with `core_wasm` deployed as TicleCore.
+async fn simulate(worker: &Worker<impl DevNetwork>, core_wasm: &[u8]) -> anyhow::Result<Simulation> {
     // create vapi
     let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
-    let worker = near_workspaces::sandbox().await?;
-    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+    let (ft_contract, owner, core_contract) = init_with_core_wasm(worker, initial_balance, core_wasm).await?;
     
     register_user(&ft_contract, core_contract.id()).await?;
 
-    let users = create_users(&worker, vec!["alice", "bob", "charlie"], vec![10, 10, 10]).await?;
+    let users = create_users(worker, vec!["alice", "bob", "charlie"], vec![10, 10, 10]).await?;
     for user in users.iter() {
         register_user(&ft_contract, user.id()).await?;
 
@@ -31,6 +88,8 @@ async fn test_gas_simulation() -> anyhow::Result<()> {
             .transact()
             .await?;
         assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
     }
 
     let alice = users.get(0).unwrap().clone();
@@ -49,15 +108,27 @@ async fn test_gas_simulation() -> anyhow::Result<()> {
     let final_storage_usage = core_contract.view_account().await?.storage_usage;
 
     let storage_used = final_storage_usage - initial_storage_usage;
-    println!("[create_vapi] Storage used: {} bytes", storage_used);
+    assert!(storage_used <= VAPI_STORAGE_BYTES, "create_vapi used {} bytes", storage_used);
+    let create_vapi_gas = res.total_gas_burnt;
 
     let amount = U128::from(NearToken::from_near(10).as_yoctonear());
     let vapi_version = "1.0";
-    let message = format!("{},{},{:?},{:?}", vapi_id, vapi_version, vec![bob.id(), charlie.id()], vec![amount, amount]);
-    let message_bytes = message.as_bytes();
+    // 서명은 nonce와 만료 시각(block timestamp, ns)을 포함한다.
+    let nonce = U64(0);
+    let expires_at = U64(worker.view_block().await?.timestamp() + 10 * 60 * 1_000_000_000);
+    let payload = ReviewRequestPayload {
+        contract_id: core_contract.id().as_str().parse()?,
+        vapi_id: vapi_id.to_string(),
+        vapi_version: vapi_version.to_string(),
+        coder_id: alice.id().as_str().parse()?,
+        reviewer_ids: vec![bob.id().as_str().parse()?, charlie.id().as_str().parse()?],
+        royalty_amounts: vec![amount.0, amount.0],
+        nonce: nonce.0,
+        expires_at: expires_at.0,
+    };
     
     let owner_secret_key = SecretKey::from_str(&owner.secret_key().to_string()).unwrap();
-    let signature = owner_secret_key.sign(message_bytes).to_string();
+    let signature = owner_secret_key.sign(&payload.hash()).to_string();
 
     let initial_storage_usage = core_contract.view_account().await?.storage_usage;
     let res = alice
@@ -67,10 +138,13 @@ async fn test_gas_simulation() -> anyhow::Result<()> {
           U128::from(NearToken::from_near(20).as_yoctonear()),
           Option::<String>::None,
           serde_json::json!({
+            "action": "request_review",
             "vapi_id": vapi_id, 
             "version": vapi_version,
             "reviewer_ids": vec![bob.id(), charlie.id()],
             "royalty_amounts": vec![amount, amount], 
+            "nonce": nonce,
+            "expires_at": expires_at,
             "signature": signature 
           }).to_string()
         ))
@@ -79,9 +153,25 @@ async fn test_gas_simulation() -> anyhow::Result<()> {
         .transact()
         .await?;
     assert!(res.is_success());
+    // 두 빌드 모두 서명 검증을 끝까지 통과해야 가스를 비교할 수 있다.
+    assert!(res.receipt_failures().is_empty());
     let final_storage_usage = core_contract.view_account().await?.storage_usage;
     let storage_used = final_storage_usage - initial_storage_usage;
-    println!("[request_review] Storage used: {} bytes", storage_used);
+    assert!(storage_used <= 2 * REVIEWER_STORAGE_BYTES, "request_review used {} bytes", storage_used);
 
-    return Ok(());
-}
\ No newline at end of file
+    let ft_on_transfer_gas = res
+        .receipt_outcomes()
+        .iter()
+        .filter(|outcome| outcome.executor_id == *core_contract.id())
+        .map(|outcome| outcome.gas_burnt.as_gas())
+        .sum::<u64>();
+    let ft_on_transfer_gas = Gas::from_gas(ft_on_transfer_gas);
+
+    return Ok(Simulation {
+        wasm_bytes: core_contract.view_code().await?.len(),
+        core_contract,
+        create_vapi_gas,
+        ft_on_transfer_gas,
+        request_review_gas: res.total_gas_burnt,
+    });
+}
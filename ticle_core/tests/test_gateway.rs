@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use near_sdk::{json_types::{U128, U64}, NearToken};
+use near_crypto::{KeyType, SecretKey};
+use serde_json::{json, Value};
+
+use ticle_core::payload::UsageReportPayload;
+
+use crate::common::utils::*;
+pub mod common;
+
+// 사용량 보고 기간 (block timestamp, ns)
+const JANUARY: u64 = 1_704_067_200_000_000_000;
+const FEBRUARY: u64 = 1_706_745_600_000_000_000;
+
+#[tokio::test]
+async fn test_usage_report() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice", "gateway", "bob"], vec![10, 10, 10]).await?;
+    for user in users.iter() {
+        register_user(&ft_contract, user.id()).await?;
+
+        let res = owner
+            .call(ft_contract.id(), "ft_transfer")
+            .args_json((user.id(), U128::from(NearToken::from_near(100).as_yoctonear()), "transfer to test account"))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
+    }
+
+    let alice = users.get(0).unwrap().clone();
+    let gateway = users.get(1).unwrap().clone();
+    let bob = users.get(2).unwrap().clone();
+
+    let vapi_id = "alice-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 같은 gateway를 쓰는 bob의 VAPI
+    let bob_vapi_id = "bob-vapi";
+    let res = bob
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": bob_vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 호출 1회당 0.001 토큰
+    let price = NearToken::from_millinear(1).as_yoctonear();
+    let res = alice
+        .call(core_contract.id(), "set_vapi_usage_price")
+        .args_json(json!({"vapi_id": vapi_id, "price": U128(price)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 1. owner가 gateway 키를 등록하고, gateway는 자신이 받은 이용료로 pool을 채운다.
+    let gateway_secret_key = SecretKey::from_random(KeyType::ED25519);
+    let res = owner
+        .call(core_contract.id(), "set_gateway_key")
+        .args_json(json!({"gateway_id": gateway.id(), "public_key": gateway_secret_key.public_key().to_string()}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let pool_amount = U128::from(NearToken::from_near(10).as_yoctonear());
+    for pool_vapi_id in [vapi_id, bob_vapi_id] {
+        let res = gateway
+            .call(ft_contract.id(), "ft_transfer_call")
+            .args_json((core_contract.id(), pool_amount, Option::<String>::None, json!({"action": "fund_gateway", "gateway_id": gateway.id(), "vapi_id": pool_vapi_id}).to_string()))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.receipt_failures().is_empty());
+    }
+
+    // 2. gateway 키로 서명한 사용량 보고는 누구나 제출할 수 있다.
+    let calls = 1000;
+    let report = UsageReportPayload {
+        contract_id: core_contract.id().as_str().parse()?,
+        gateway_id: gateway.id().as_str().parse()?,
+        vapi_id: vapi_id.to_string(),
+        period_start: JANUARY,
+        period_end: FEBRUARY,
+        calls,
+        price,
+    };
+    let args = |price: u128, signature: String| json!({
+        "report": {
+            "gateway_id": gateway.id(),
+            "vapi_id": vapi_id,
+            "period_start": U64(JANUARY),
+            "period_end": U64(FEBRUARY),
+            "calls": U64(calls),
+            "price": U128(price),
+        },
+        "signature": signature,
+    });
+
+    // gateway 키가 아닌 키로 서명하면 거절된다.
+    let owner_secret_key = SecretKey::from_str(&owner.secret_key().to_string()).unwrap();
+    let res = gateway
+        .call(core_contract.id(), "submit_usage_report")
+        .args_json(args(price, owner_secret_key.sign(&report.hash()).to_string()))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Invalid signature"));
+
+    // 코더가 서명 이후에 가격을 올리면 그 보고는 거절되고, pool은 그대로 남는다.
+    let signature = gateway_secret_key.sign(&report.hash()).to_string();
+    let set_price = |price: u128| alice
+        .call(core_contract.id(), "set_vapi_usage_price")
+        .args_json(json!({"vapi_id": vapi_id, "price": U128(price)}))
+        .max_gas()
+        .transact();
+    assert!(set_price(price * 1000).await?.is_success());
+
+    let res = gateway
+        .call(core_contract.id(), "submit_usage_report")
+        .args_json(args(price, signature.clone()))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Usage price changed since the report was signed"));
+
+    // 올린 가격으로 서명해도 그 VAPI의 pool을 넘어서는 금액은 다른 VAPI의 pool에서 가져올 수 없다.
+    let repriced_report = UsageReportPayload { price: price * 1000, ..report.clone() };
+    let res = gateway
+        .call(core_contract.id(), "submit_usage_report")
+        .args_json(args(price * 1000, gateway_secret_key.sign(&repriced_report.hash()).to_string()))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Not enough balance in the gateway pool of the VAPI"));
+
+    let bob_pool = core_contract
+        .call("get_gateway_pool")
+        .args_json(json!({"gateway_id": gateway.id(), "vapi_id": bob_vapi_id}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(bob_pool, pool_amount);
+
+    assert!(set_price(price).await?.is_success());
+    let res = gateway
+        .call(core_contract.id(), "submit_usage_report")
+        .args_json(args(price, signature.clone()))
+        .max_gas()
+        .transact()
+        .await?;
+    res.logs().iter().for_each(|log| println!("{:?}", log));
+    assert!(res.receipt_failures().is_empty());
+
+    let amount = price * calls as u128;
+    let earnings = core_contract
+        .call("coder_earnings")
+        .args_json(json!({"account_id": alice.id()}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    assert_eq!(earnings["total_unclaimed_amount"], json!((amount * 60 / 100).to_string()));
+
+    let view = core_contract
+        .call("get_gateway")
+        .args_json(json!({"gateway_id": gateway.id()}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    assert_eq!(view["balance"], json!((pool_amount.0 * 2 - amount).to_string()));
+    assert_eq!(view["total_settled"], json!(amount.to_string()));
+
+    // 3. 같은 보고를 다시 제출하면 거절된다.
+    let res = gateway
+        .call(core_contract.id(), "submit_usage_report")
+        .args_json(args(price, signature))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Usage period overlaps the previous report"));
+
+    // 4. 남은 pool은 gateway가 VAPI별로 돌려받을 수 있다.
+    for pool_vapi_id in [vapi_id, bob_vapi_id] {
+        let res = gateway
+            .call(core_contract.id(), "withdraw_gateway_pool")
+            .args_json(json!({"vapi_id": pool_vapi_id}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.receipt_failures().is_empty());
+    }
+
+    let view = core_contract
+        .call("get_gateway")
+        .args_json(json!({"gateway_id": gateway.id()}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    assert_eq!(view["balance"], json!("0"));
+
+    return Ok(());
+}
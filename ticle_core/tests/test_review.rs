@@ -1,9 +1,11 @@
 use std::str::FromStr;
 
-use near_sdk::{json_types::U128, NearToken};
+use near_sdk::{json_types::{U128, U64}, NearToken};
 use serde_json::json;
 use near_crypto::SecretKey;
 
+use ticle_core::payload::ReviewRequestPayload;
+
 use crate::common::utils::*;
 pub mod common;
 
@@ -30,6 +32,8 @@ async fn test_review() -> anyhow::Result<()> {
             .transact()
             .await?;
         assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
     }
 
     let alice = users.get(0).unwrap().clone();
@@ -53,13 +57,24 @@ async fn test_review() -> anyhow::Result<()> {
     // 4. 오프체인 서버에서 ed25519 알고리즘으로 서명을 생성한다.
     let amount = U128::from(NearToken::from_near(10).as_yoctonear());
     let vapi_version = "1.0";
-    let message = format!("{},{},{:?},{:?}", vapi_id, vapi_version, vec![bob.id(), charlie.id()], vec![amount, amount]);
-    let message_bytes = message.as_bytes();
+    // 서명은 nonce와 만료 시각(block timestamp, ns)을 포함한다.
+    let nonce = U64(0);
+    let expires_at = U64(worker.view_block().await?.timestamp() + 10 * 60 * 1_000_000_000);
+    let payload = ReviewRequestPayload {
+        contract_id: core_contract.id().as_str().parse()?,
+        vapi_id: vapi_id.to_string(),
+        vapi_version: vapi_version.to_string(),
+        coder_id: alice.id().as_str().parse()?,
+        reviewer_ids: vec![bob.id().as_str().parse()?, charlie.id().as_str().parse()?],
+        royalty_amounts: vec![amount.0, amount.0],
+        nonce: nonce.0,
+        expires_at: expires_at.0,
+    };
     
     let owner_secret_key = SecretKey::from_str(&owner.secret_key().to_string()).unwrap();
 
     // ex) ed25519:4FpUmQQstsqxH2gnwTajRyizbkanBqSkiBtX3g9wY5ngwFGfQPe5utw6EhwHVjx9sB7wY4F6S6TwReTCFR9u5ZtY
-    let signature = owner_secret_key.sign(message_bytes).to_string();
+    let signature = owner_secret_key.sign(&payload.hash()).to_string();
     
     // 5. alice는 서버의 서명과 함께 트랜잭션을 전송한다.
     let res = alice
@@ -69,10 +84,13 @@ async fn test_review() -> anyhow::Result<()> {
           U128::from(NearToken::from_near(20).as_yoctonear()),
           Option::<String>::None,
           serde_json::json!({
+            "action": "request_review",
             "vapi_id": vapi_id, 
             "version": vapi_version,
             "reviewer_ids": vec![bob.id(), charlie.id()],
             "royalty_amounts": vec![amount, amount], 
+            "nonce": nonce,
+            "expires_at": expires_at,
             "signature": signature 
           }).to_string()
         ))
@@ -83,7 +101,39 @@ async fn test_review() -> anyhow::Result<()> {
     res.logs().iter().for_each(|log| println!("{:?}", log));
     assert!(res.is_success());
 
-    // 6. 리뷰어들은 2주가 지나지 않으면 리워드 수령이 불가능하다.
+    // 같은 서명을 다시 보내면 nonce가 이미 사용되었으므로 거절되고 토큰은 환불된다.
+    let used = core_contract
+        .call("is_nonce_used")
+        .args_json(json!({"coder_id": alice.id(), "nonce": nonce}))
+        .view()
+        .await?
+        .json::<bool>()?;
+    assert!(used);
+
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((
+          core_contract.id(),
+          U128::from(NearToken::from_near(20).as_yoctonear()),
+          Option::<String>::None,
+          serde_json::json!({
+            "action": "request_review",
+            "vapi_id": vapi_id,
+            "version": vapi_version,
+            "reviewer_ids": vec![bob.id(), charlie.id()],
+            "royalty_amounts": vec![amount, amount],
+            "nonce": nonce,
+            "expires_at": expires_at,
+            "signature": signature
+          }).to_string()
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Nonce already used"));
+
+    // 6. 리뷰를 제출하지 않은 리뷰어는 리워드를 수령할 수 없다.
     let res = bob
         .call(core_contract.id(), "claim_review_reward")
         .args_json(json!({"vapi_id": vapi_id}))
@@ -93,7 +143,46 @@ async fn test_review() -> anyhow::Result<()> {
     res.logs().iter().for_each(|log| println!("{:?}", log));
     assert!(res.is_failure());
 
-    // 7. 2주가 지난 뒤 리뷰어들이 리워드 수령이 가능하다.
+    // 7. bob, charlie는 리뷰를 수락하고 제출한다.
+    for reviewer in [&bob, &charlie] {
+        for method in ["accept_review", "submit_review"] {
+            let res = reviewer
+                .call(core_contract.id(), method)
+                .args_json(json!({"vapi_id": vapi_id}))
+                .max_gas()
+                .transact()
+                .await?;
+            assert!(res.is_success());
+        }
+    }
+
+    // 8. alice는 charlie의 리뷰만 승인한다. 승인된 리뷰는 바로 수령할 수 있다.
+    let res = alice
+        .call(core_contract.id(), "approve_review")
+        .args_json(json!({"vapi_id": vapi_id, "reviewer_id": charlie.id()}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = charlie
+        .call(core_contract.id(), "claim_review_reward")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 9. 승인되지 않은 bob의 리뷰는 승인 대기 기간(2주)이 지나기 전에는 수령할 수 없다.
+    let res = bob
+        .call(core_contract.id(), "claim_review_reward")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    // 10. 2주가 지나면 코더가 승인하지 않아도 리워드 수령이 가능하다.
     // 재단에 fast_forward 문의 필요.
     // https://docs.near.org/sdk/rust/testing/integration-tests#fast-forwarding---fast-forward-to-a-future-block
     const TWO_WEEKS: u64 = 60 * 60 * 24 * 14;
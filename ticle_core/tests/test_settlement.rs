@@ -0,0 +1,729 @@
+use near_sdk::{json_types::{Base58CryptoHash, U128, U64}, NearToken};
+use near_workspaces::{result::ExecutionFinalResult, Account, Contract};
+use serde_json::{json, Value};
+use ticle_core::merkle::{merkle_proof, merkle_root, SettlementLeaf};
+
+use crate::common::utils::*;
+pub mod common;
+
+// 정산 기간 (block timestamp, ns)
+const JANUARY: u64 = 1_704_067_200_000_000_000;
+const FEBRUARY: u64 = 1_706_745_600_000_000_000;
+const MARCH: u64 = 1_709_251_200_000_000_000;
+
+#[tokio::test]
+async fn test_zero_stake_settlement() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice", "bob"], vec![10, 10]).await?;
+    for user in users.iter() {
+        register_user(&ft_contract, user.id()).await?;
+
+        let res = owner
+            .call(ft_contract.id(), "ft_transfer")
+            .args_json((user.id(), U128::from(NearToken::from_near(100).as_yoctonear()), "transfer to test account"))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
+    }
+
+    let alice = users.get(0).unwrap().clone();
+    let bob = users.get(1).unwrap().clone();
+
+    for vapi_id in ["undistributed-vapi", "coder-vapi"] {
+        let res = alice
+            .call(core_contract.id(), "create_vapi")
+            .args_json(json!({"vapi_id": vapi_id}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    // 정산된 delegator 몫이 바로 풀리도록 지급 기간을 없앤다.
+    let res = owner
+        .call(core_contract.id(), "set_reward_release_period")
+        .args_json(json!({"vapi_id": "undistributed-vapi", "reward_release_period": U64(0)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let transfer_balance = U128::from(NearToken::from_near(10).as_yoctonear());
+
+    // 1. 기본 정책(Undistributed)에서는 예치금이 없는 VAPI를 정산해도 실패하지 않고,
+    //    delegator 몫(39%)은 처음 입금한 delegator에게 돌아간다.
+    let policy = core_contract.call("get_zero_stake_policy").view().await?.json::<String>()?;
+    assert_eq!(policy, "Undistributed");
+
+    settle(&owner, &ft_contract, &core_contract, "undistributed-vapi", transfer_balance).await?;
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "undistributed-vapi").await?, 6_000_000_000_000_000_000_000_000);
+
+    let unreleased = core_contract
+        .call("unreleased_reward")
+        .args_json(json!({"vapi_id": "undistributed-vapi"}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(unreleased.0, 3_900_000_000_000_000_000_000_000);
+
+    let res = bob
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": "undistributed-vapi" }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let bob_reward = core_contract
+        .call("pending_reward")
+        .args_json((bob.id(), "undistributed-vapi"))
+        .view()
+        .await?
+        .json::<u128>()?;
+    assert_eq!(bob_reward, 3_900_000_000_000_000_000_000_000);
+
+    // 2. owner만 정책을 바꿀 수 있다.
+    let res = alice
+        .call(core_contract.id(), "set_zero_stake_policy")
+        .args_json(json!({"policy": "Coder"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = owner
+        .call(core_contract.id(), "set_zero_stake_policy")
+        .args_json(json!({"policy": "Coder"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 3. Coder 정책에서는 delegator 몫이 코더에게 간다. (60% + 39%)
+    settle(&owner, &ft_contract, &core_contract, "coder-vapi", transfer_balance).await?;
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "coder-vapi").await?, 9_900_000_000_000_000_000_000_000);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_fee_schedule() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice"], vec![10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    deposit_storage(&core_contract, &alice, NearToken::from_near(1)).await?;
+
+    let vapi_id = "alice-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 기본 수수료: delegator 39%, burn 1%, coder 60%
+    let schedule = get_fee_schedule(&core_contract, vapi_id).await?;
+    assert_eq!(schedule, json!({"coder_bps": 6000, "delegator_bps": 3900, "burn_bps": 100, "treasury_bps": 0}));
+
+    // burn과 delegator 몫의 합이 100%를 넘는 설정은 거절된다.
+    let res = owner
+        .call(core_contract.id(), "set_fee_config")
+        .args_json(json!({"fee_config": {"delegator_bps": 3000, "burn_bps": 500, "max_delegator_bps": 9800}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = owner
+        .call(core_contract.id(), "set_fee_config")
+        .args_json(json!({"fee_config": {"delegator_bps": 3000, "burn_bps": 200, "max_delegator_bps": 5000}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 코더는 제한 안에서만 delegator 몫을 늘릴 수 있다.
+    for (delegator_bps, success) in [(2000, false), (6000, false), (4500, true)] {
+        let res = alice
+            .call(core_contract.id(), "set_vapi_delegator_bps")
+            .args_json(json!({"vapi_id": vapi_id, "delegator_bps": delegator_bps}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert_eq!(res.is_success(), success);
+    }
+
+    let schedule = get_fee_schedule(&core_contract, vapi_id).await?;
+    assert_eq!(schedule, json!({"coder_bps": 5300, "delegator_bps": 4500, "burn_bps": 200, "treasury_bps": 0}));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_treasury() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice", "treasury"], vec![10, 10]).await?;
+    for user in users.iter() {
+        register_user(&ft_contract, user.id()).await?;
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
+    }
+
+    let alice = users.get(0).unwrap().clone();
+    let treasury = users.get(1).unwrap().clone();
+
+    let vapi_id = "alice-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // treasury 5%, 예치금이 없는 VAPI의 delegator 몫도 treasury로 보낸다.
+    let res = owner
+        .call(core_contract.id(), "set_fee_config")
+        .args_json(json!({"fee_config": {"delegator_bps": 3900, "burn_bps": 100, "max_delegator_bps": 9000, "treasury_bps": 500}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    for (method, args) in [
+        ("set_treasury", json!({"treasury_id": treasury.id()})),
+        ("set_zero_stake_policy", json!({"policy": "Treasury"})),
+    ] {
+        let res = owner
+            .call(core_contract.id(), method)
+            .args_json(args)
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    // 10토큰 정산: treasury 0.5 + delegator 몫 3.9
+    let transfer_balance = U128::from(NearToken::from_near(10).as_yoctonear());
+    settle(&owner, &ft_contract, &core_contract, vapi_id, transfer_balance).await?;
+
+    let treasury_amount: u128 = 4_400_000_000_000_000_000_000_000;
+    let vapi_treasury_amount = core_contract
+        .call("get_vapi_treasury_amount")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(vapi_treasury_amount.0, treasury_amount);
+    assert_eq!(coder_unclaimed(&core_contract, &alice, vapi_id).await?, 5_500_000_000_000_000_000_000_000);
+
+    // treasury 계정만 출금할 수 있다.
+    let res = alice
+        .call(core_contract.id(), "withdraw_treasury")
+        .args_json(json!({}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = treasury
+        .call(core_contract.id(), "withdraw_treasury")
+        .args_json(json!({}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let balance = ft_contract
+        .call("ft_balance_of")
+        .args_json(json!({"account_id": treasury.id()}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(balance.0, treasury_amount);
+
+    let view = core_contract.call("get_treasury").view().await?.json::<Value>()?;
+    assert_eq!(view["balance"], json!("0"));
+    assert_eq!(view["total_collected"], json!(treasury_amount.to_string()));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_settlement_batches() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice"], vec![10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    deposit_storage(&core_contract, &alice, NearToken::from_near(1)).await?;
+
+    let vapi_id = "alice-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = owner
+        .call(core_contract.id(), "set_zero_stake_policy")
+        .args_json(json!({"policy": "Coder"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let transfer_balance = U128::from(NearToken::from_near(10).as_yoctonear());
+    let res = send_settlement(&owner, &ft_contract, &core_contract, "2024-01", (JANUARY, FEBRUARY), vapi_id, transfer_balance).await?;
+    assert!(res.receipt_failures().is_empty());
+
+    let used = core_contract
+        .call("is_settlement_batch_used")
+        .args_json(json!({"batch_id": "2024-01"}))
+        .view()
+        .await?
+        .json::<bool>()?;
+    assert!(used);
+
+    // 같은 batch를 다시 보내면 거절되고 토큰은 환불된다.
+    let res = send_settlement(&owner, &ft_contract, &core_contract, "2024-01", (JANUARY, FEBRUARY), vapi_id, transfer_balance).await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement batch already processed"));
+
+    // batch_id가 달라도 이미 정산된 기간과 겹치면 거절된다.
+    let res = send_settlement(&owner, &ft_contract, &core_contract, "2024-01-retry", (JANUARY, FEBRUARY), vapi_id, transfer_balance).await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement period overlaps"));
+
+    let res = send_settlement(&owner, &ft_contract, &core_contract, "2024-02", (FEBRUARY, MARCH), vapi_id, transfer_balance).await?;
+    assert!(res.receipt_failures().is_empty());
+
+    assert_eq!(coder_unclaimed(&core_contract, &alice, vapi_id).await?, 2 * 9_900_000_000_000_000_000_000_000);
+
+    let history = core_contract
+        .call("get_settlement_history")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .view()
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["batch_id"], json!("2024-01"));
+    assert_eq!(history[0]["period_end"], json!(FEBRUARY.to_string()));
+    assert_eq!(history[1]["batch_id"], json!("2024-02"));
+    assert_eq!(history[1]["amount"], json!(transfer_balance));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_settlement_burn() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice"], vec![10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    deposit_storage(&core_contract, &alice, NearToken::from_near(1)).await?;
+
+    let vapi_id = "alice-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = owner
+        .call(core_contract.id(), "set_zero_stake_policy")
+        .args_json(json!({"policy": "Coder"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let total_supply = ft_total_supply(&ft_contract).await?;
+    let transfer_balance = U128::from(NearToken::from_near(10).as_yoctonear());
+    let res = send_settlement(&owner, &ft_contract, &core_contract, "2024-01", (JANUARY, FEBRUARY), vapi_id, transfer_balance).await?;
+    assert!(res.receipt_failures().is_empty());
+    assert!(res.logs().iter().any(|log| log.contains(r#""event":"ft_burn""#)));
+
+    // 소각은 정산을 보낸 owner가 아니라 토큰을 보관하고 있는 TicleCore의 잔액에서 이루어진다.
+    let burn_amount = transfer_balance.0 / 100;
+    assert_eq!(ft_total_supply(&ft_contract).await?, total_supply - burn_amount);
+    assert_eq!(ft_balance_of(&ft_contract, owner.id()).await?, initial_balance.0 - transfer_balance.0);
+    assert_eq!(ft_balance_of(&ft_contract, core_contract.id()).await?, transfer_balance.0 - burn_amount);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_chunked_settlement() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice"], vec![20]).await?;
+    let alice = users.get(0).unwrap().clone();
+    deposit_storage(&core_contract, &alice, NearToken::from_near(10)).await?;
+
+    let res = owner
+        .call(core_contract.id(), "set_zero_stake_policy")
+        .args_json(json!({"policy": "Coder"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 한 페이지(20개)보다 많은 VAPI를 한 번에 정산한다.
+    let vapi_ids: Vec<String> = (0..25).map(|index| format!("vapi-{}", index)).collect();
+    for vapi_id in vapi_ids.iter() {
+        let res = alice
+            .call(core_contract.id(), "create_vapi")
+            .args_json(json!({"vapi_id": vapi_id}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    let amount = U128::from(NearToken::from_near(1).as_yoctonear());
+    let amounts = vec![amount; vapi_ids.len()];
+    let total_amount = U128(amount.0 * vapi_ids.len() as u128);
+
+    // 1. 금액의 합이 전송한 토큰과 다르면 등록되지 않는다.
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), U128(total_amount.0 - 1), Option::<String>::None, json!({
+            "action": "settlement",
+            "batch_id": "2024-01",
+            "period_start": U64(JANUARY),
+            "period_end": U64(FEBRUARY),
+            "vapi_ids": vapi_ids,
+            "amounts": amounts
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement amounts must add up to the transferred amount"));
+
+    // 2. 등록과 함께 첫 페이지만 적용되고, 나머지는 대기한다.
+    let total_supply = ft_total_supply(&ft_contract).await?;
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), total_amount, Option::<String>::None, json!({
+            "action": "settlement",
+            "batch_id": "2024-01",
+            "period_start": U64(JANUARY),
+            "period_end": U64(FEBRUARY),
+            "vapi_ids": vapi_ids,
+            "amounts": amounts
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.receipt_failures().is_empty());
+
+    let batch = get_settlement_batch(&core_contract, "2024-01").await?;
+    assert_eq!(batch["processed"], json!("20"));
+    assert_eq!(batch["total_entries"], json!("25"));
+    assert_eq!(batch["completed"], json!(false));
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "vapi-19").await?, 990_000_000_000_000_000_000_000);
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "vapi-20").await?, 0);
+    assert_eq!(ft_total_supply(&ft_contract).await?, total_supply);
+
+    // 3. 누구나 나머지를 처리할 수 있고, 마지막 페이지가 끝나면 소각된다.
+    let res = alice
+        .call(core_contract.id(), "process_settlement")
+        .args_json(json!({"batch_id": "2024-01"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.receipt_failures().is_empty());
+
+    let batch = get_settlement_batch(&core_contract, "2024-01").await?;
+    assert_eq!(batch["processed"], json!("25"));
+    assert_eq!(batch["completed"], json!(true));
+    assert_eq!(batch["burn_amount"], json!((total_amount.0 / 100).to_string()));
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "vapi-24").await?, 990_000_000_000_000_000_000_000);
+    assert_eq!(ft_total_supply(&ft_contract).await?, total_supply - total_amount.0 / 100);
+
+    let res = alice
+        .call(core_contract.id(), "process_settlement")
+        .args_json(json!({"batch_id": "2024-01"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement batch already completed"));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_merkle_settlement() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice"], vec![10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    deposit_storage(&core_contract, &alice, NearToken::from_near(1)).await?;
+
+    let res = owner
+        .call(core_contract.id(), "set_zero_stake_policy")
+        .args_json(json!({"policy": "Coder"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let vapi_ids = ["vapi-a", "vapi-b", "vapi-c"];
+    for vapi_id in vapi_ids {
+        let res = alice
+            .call(core_contract.id(), "create_vapi")
+            .args_json(json!({"vapi_id": vapi_id}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    // 정산 서버가 만드는 것과 같은 트리를 만든다.
+    let leaves: Vec<SettlementLeaf> = vapi_ids
+        .iter()
+        .enumerate()
+        .map(|(index, vapi_id)| SettlementLeaf {
+            vapi_id: vapi_id.to_string(),
+            amount: NearToken::from_near(index as u128 + 1).as_yoctonear(),
+        })
+        .collect();
+    let total_amount: u128 = leaves.iter().map(|leaf| leaf.amount).sum();
+
+    // 1. root와 전체 금액만 올린다.
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), U128(total_amount), Option::<String>::None, json!({
+            "action": "settlement_epoch",
+            "epoch_id": "2024-01",
+            "period_start": U64(JANUARY),
+            "period_end": U64(FEBRUARY),
+            "merkle_root": Base58CryptoHash::from(merkle_root(&leaves))
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.receipt_failures().is_empty());
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "vapi-b").await?, 0);
+
+    // 2. 누구나 proof로 VAPI별 몫을 청구할 수 있다.
+    let proof: Vec<Base58CryptoHash> = merkle_proof(&leaves, 1).into_iter().map(Base58CryptoHash::from).collect();
+    let claim = |amount: u128| json!({"epoch_id": "2024-01", "vapi_id": "vapi-b", "amount": U128(amount), "proof": proof});
+
+    let res = alice
+        .call(core_contract.id(), "claim_settlement")
+        .args_json(claim(leaves[1].amount + 1))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Invalid Merkle proof"));
+
+    let res = alice
+        .call(core_contract.id(), "claim_settlement")
+        .args_json(claim(leaves[1].amount))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.receipt_failures().is_empty());
+    assert_eq!(coder_unclaimed(&core_contract, &alice, "vapi-b").await?, leaves[1].amount * 99 / 100);
+
+    let res = alice
+        .call(core_contract.id(), "claim_settlement")
+        .args_json(claim(leaves[1].amount))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement already claimed"));
+
+    let epoch = core_contract
+        .call("get_settlement_epoch")
+        .args_json(json!({"epoch_id": "2024-01"}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    assert_eq!(epoch["total_amount"], json!(total_amount.to_string()));
+    assert_eq!(epoch["claimed_amount"], json!(leaves[1].amount.to_string()));
+
+    let history = core_contract
+        .call("get_settlement_history")
+        .args_json(json!({"vapi_id": "vapi-b"}))
+        .view()
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["batch_id"], json!("2024-01"));
+
+    // 3. 다음 epoch는 이전 epoch와 기간이 겹치면 안 된다.
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), U128(total_amount), Option::<String>::None, json!({
+            "action": "settlement_epoch",
+            "epoch_id": "2024-01-retry",
+            "period_start": U64(JANUARY),
+            "period_end": U64(FEBRUARY),
+            "merkle_root": Base58CryptoHash::from(merkle_root(&leaves))
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement period overlaps the previous epoch"));
+
+    // 4. batch로 정산된 기간과 겹치는 epoch도 거절된다. 올라갔다면 vapi-a는 그 몫을 청구할 수 없다.
+    let amount = U128::from(NearToken::from_near(1).as_yoctonear());
+    let res = send_settlement(&owner, &ft_contract, &core_contract, "vapi-a-2024-02", (FEBRUARY, MARCH), "vapi-a", amount).await?;
+    assert!(res.receipt_failures().is_empty());
+
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), U128(total_amount), Option::<String>::None, json!({
+            "action": "settlement_epoch",
+            "epoch_id": "2024-02",
+            "period_start": U64(FEBRUARY),
+            "period_end": U64(MARCH),
+            "merkle_root": Base58CryptoHash::from(merkle_root(&leaves))
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Settlement period overlaps a settlement batch"));
+
+    let epoch = core_contract
+        .call("get_settlement_epoch")
+        .args_json(json!({"epoch_id": "2024-02"}))
+        .view()
+        .await?
+        .json::<Option<Value>>()?;
+    assert!(epoch.is_none());
+
+    return Ok(());
+}
+
+async fn get_settlement_batch(core_contract: &Contract, batch_id: &str) -> anyhow::Result<Value> {
+    let batch = core_contract
+        .call("get_settlement_batch")
+        .args_json(json!({"batch_id": batch_id}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    return Ok(batch);
+}
+
+async fn ft_total_supply(ft_contract: &Contract) -> anyhow::Result<u128> {
+    let total_supply = ft_contract.call("ft_total_supply").view().await?.json::<U128>()?;
+    return Ok(total_supply.0);
+}
+
+async fn ft_balance_of(ft_contract: &Contract, account_id: &near_workspaces::AccountId) -> anyhow::Result<u128> {
+    let balance = ft_contract
+        .call("ft_balance_of")
+        .args_json(json!({"account_id": account_id}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    return Ok(balance.0);
+}
+
+async fn get_fee_schedule(core_contract: &Contract, vapi_id: &str) -> anyhow::Result<Value> {
+    let schedule = core_contract
+        .call("get_fee_schedule")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    return Ok(schedule);
+}
+
+async fn settle(owner: &Account, ft_contract: &Contract, core_contract: &Contract, vapi_id: &str, amount: U128) -> anyhow::Result<()> {
+    let res = send_settlement(owner, ft_contract, core_contract, &format!("{}-2024-01", vapi_id), (JANUARY, FEBRUARY), vapi_id, amount).await?;
+    res.logs().iter().for_each(|log| println!("{:?}", log));
+    assert!(res.is_success());
+    assert!(res.receipt_failures().is_empty());
+
+    return Ok(());
+}
+
+async fn send_settlement(
+    owner: &Account,
+    ft_contract: &Contract,
+    core_contract: &Contract,
+    batch_id: &str,
+    (period_start, period_end): (u64, u64),
+    vapi_id: &str,
+    amount: U128,
+) -> anyhow::Result<ExecutionFinalResult> {
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), amount, Option::<String>::None, serde_json::json!({
+            "action": "settlement",
+            "batch_id": batch_id,
+            "period_start": U64(period_start),
+            "period_end": U64(period_end),
+            "vapi_ids": vec![vapi_id],
+            "amounts": vec![amount]
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+
+    return Ok(res);
+}
+
+async fn coder_unclaimed(core_contract: &Contract, coder: &Account, vapi_id: &str) -> anyhow::Result<u128> {
+    let earnings = core_contract
+        .call("coder_earnings")
+        .args_json(json!({"account_id": coder.id()}))
+        .view()
+        .await?
+        .json::<Value>()?;
+    let vapi = earnings["vapis"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|vapi| vapi["vapi_id"] == vapi_id)
+        .unwrap();
+    return Ok(vapi["unclaimed_reward_amount"].as_str().unwrap().parse()?);
+}
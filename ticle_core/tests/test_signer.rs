@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use near_sdk::{json_types::{U128, U64}, NearToken};
+use serde_json::json;
+use near_crypto::{KeyType, SecretKey};
+
+use ticle_core::payload::ReviewRequestPayload;
+
+use crate::common::utils::*;
+pub mod common;
+
+#[tokio::test]
+async fn test_signer_threshold() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice", "bob"], vec![10, 10]).await?;
+    for user in users.iter() {
+        register_user(&ft_contract, user.id()).await?;
+
+        let res = owner
+            .call(ft_contract.id(), "ft_transfer")
+            .args_json((user.id(), U128::from(NearToken::from_near(100).as_yoctonear()), "transfer to test account"))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
+    }
+
+    let alice = users.get(0).unwrap().clone();
+    let bob = users.get(1).unwrap().clone();
+
+    let vapi_id = "alice-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 두 번째 서명 키를 등록하고 2-of-2로 설정한다.
+    let owner_secret_key = SecretKey::from_str(&owner.secret_key().to_string()).unwrap();
+    let second_secret_key = SecretKey::from_random(KeyType::ED25519);
+    let res = owner
+        .call(core_contract.id(), "add_signer_key")
+        .args_json(json!({"public_key": second_secret_key.public_key().to_string()}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 등록된 키 수보다 큰 threshold는 설정할 수 없다.
+    let res = owner
+        .call(core_contract.id(), "set_signer_threshold")
+        .args_json(json!({"threshold": 3}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = owner
+        .call(core_contract.id(), "set_signer_threshold")
+        .args_json(json!({"threshold": 2}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 코더는 자신의 VAPI threshold를 낮출 수 없다.
+    let res = alice
+        .call(core_contract.id(), "set_vapi_signer_threshold")
+        .args_json(json!({"vapi_id": vapi_id, "threshold": 1}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let threshold = core_contract
+        .call("get_signer_threshold")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .view()
+        .await?
+        .json::<u8>()?;
+    assert_eq!(threshold, 2);
+
+    let amount = U128::from(NearToken::from_near(10).as_yoctonear());
+    let vapi_version = "1.0";
+    let nonce = U64(0);
+    let expires_at = U64(worker.view_block().await?.timestamp() + 10 * 60 * 1_000_000_000);
+    let payload = ReviewRequestPayload {
+        contract_id: core_contract.id().as_str().parse()?,
+        vapi_id: vapi_id.to_string(),
+        vapi_version: vapi_version.to_string(),
+        coder_id: alice.id().as_str().parse()?,
+        reviewer_ids: vec![bob.id().as_str().parse()?],
+        royalty_amounts: vec![amount.0],
+        nonce: nonce.0,
+        expires_at: expires_at.0,
+    };
+
+    let owner_signature = owner_secret_key.sign(&payload.hash()).to_string();
+    let second_signature = second_secret_key.sign(&payload.hash()).to_string();
+
+    let cases = vec![
+        // 서명이 하나뿐이면 거절된다.
+        (json!({"signature": owner_signature}), "Not enough signatures"),
+        // 같은 키의 서명을 두 번 보내도 한 명으로 센다.
+        (json!({"signatures": [owner_signature, owner_signature]}), "Duplicate signer key"),
+    ];
+    for (signatures, error) in cases {
+        let mut msg = json!({
+            "action": "request_review",
+            "vapi_id": vapi_id,
+            "version": vapi_version,
+            "reviewer_ids": vec![bob.id()],
+            "royalty_amounts": vec![amount],
+            "nonce": nonce,
+            "expires_at": expires_at,
+        });
+        msg.as_object_mut().unwrap().extend(signatures.as_object().unwrap().clone());
+
+        let res = alice
+            .call(ft_contract.id(), "ft_transfer_call")
+            .args_json((core_contract.id(), amount, Option::<String>::None, msg.to_string()))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(format!("{:?}", res.receipt_failures()).contains(error));
+    }
+
+    // 서로 다른 두 키의 서명이 있으면 리뷰 요청이 기록된다.
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((
+          core_contract.id(),
+          amount,
+          Option::<String>::None,
+          serde_json::json!({
+            "action": "request_review",
+            "vapi_id": vapi_id,
+            "version": vapi_version,
+            "reviewer_ids": vec![bob.id()],
+            "royalty_amounts": vec![amount],
+            "nonce": nonce,
+            "expires_at": expires_at,
+            "signatures": [owner_signature, second_signature]
+          }).to_string()
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(res.receipt_failures().is_empty());
+
+    let used = core_contract
+        .call("is_nonce_used")
+        .args_json(json!({"coder_id": alice.id(), "nonce": nonce}))
+        .view()
+        .await?
+        .json::<bool>()?;
+    assert!(used);
+
+    return Ok(());
+}
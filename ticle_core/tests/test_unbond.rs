@@ -0,0 +1,151 @@
+use near_sdk::{json_types::{U128, U64}, NearToken};
+use serde_json::{json, Value};
+
+use crate::common::utils::*;
+pub mod common;
+
+#[tokio::test]
+async fn test_unbond() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner, core_contract) = init(&worker, initial_balance).await?;
+
+    register_user(&ft_contract, core_contract.id()).await?;
+
+    let users = create_users(&worker, vec!["alice"], vec![10]).await?;
+    for user in users.iter() {
+        register_user(&ft_contract, user.id()).await?;
+
+        let res = owner
+            .call(ft_contract.id(), "ft_transfer")
+            .args_json((user.id(), U128::from(NearToken::from_near(100).as_yoctonear()), "transfer to test account"))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
+    }
+
+    let alice = users.get(0).unwrap().clone();
+
+    let vapi_id = "test-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // alice가 10토큰을 VAPI에 입금
+    let transfer_balance = U128::from(NearToken::from_near(10).as_yoctonear());
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": vapi_id }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let origin_balance = ft_balance_of(&ft_contract, alice.id()).await?;
+
+    // 1. 4토큰을 출금하면 바로 전송되지 않고 unbonding 기간(기본 7일) 동안 잠긴다.
+    let withdraw_amount = U128::from(NearToken::from_near(4).as_yoctonear());
+    let res = alice
+        .call(core_contract.id(), "withdraw")
+        .args_json(json!({"vapi_id": vapi_id, "amount": withdraw_amount}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(ft_balance_of(&ft_contract, alice.id()).await?, origin_balance);
+
+    let unbonds = get_unbonds(&core_contract, &alice, vapi_id).await?;
+    assert_eq!(unbonds.len(), 1);
+    assert_eq!(unbonds[0]["amount"], json!(withdraw_amount));
+    assert_eq!(unbonds[0]["unlocked"], json!(false));
+
+    let res = alice
+        .call(core_contract.id(), "complete_unbond")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    // 남은 예치금(6)보다 많이 출금할 수 없다.
+    let res = alice
+        .call(core_contract.id(), "withdraw")
+        .args_json(json!({"vapi_id": vapi_id, "amount": U128::from(NearToken::from_near(7).as_yoctonear())}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    // 2. owner가 unbonding 기간을 없애면 이후 출금분은 바로 수령할 수 있다.
+    let res = owner
+        .call(core_contract.id(), "set_unbonding_period")
+        .args_json(json!({"vapi_id": vapi_id, "unbonding_period": U64(0)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let withdraw_amount = U128::from(NearToken::from_near(2).as_yoctonear());
+    let res = alice
+        .call(core_contract.id(), "withdraw")
+        .args_json(json!({"vapi_id": vapi_id, "amount": withdraw_amount}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(core_contract.id(), "complete_unbond")
+        .args_json(json!({"vapi_id": vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(ft_balance_of(&ft_contract, alice.id()).await?, origin_balance + withdraw_amount.0);
+
+    // 처음 출금한 4토큰은 여전히 잠겨 있다.
+    let unbonds = get_unbonds(&core_contract, &alice, vapi_id).await?;
+    assert_eq!(unbonds.len(), 1);
+    assert_eq!(unbonds[0]["amount"], json!(U128::from(NearToken::from_near(4).as_yoctonear())));
+
+    // VAPI를 지정하지 않으면 계정의 모든 VAPI에서 unbond를 찾는다.
+    let unbonds = core_contract
+        .call("get_unbonds")
+        .args_json(json!({"account_id": alice.id()}))
+        .view()
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(unbonds.len(), 1);
+    assert_eq!(unbonds[0]["vapi_id"], json!(vapi_id));
+
+    return Ok(());
+}
+
+async fn ft_balance_of(ft_contract: &near_workspaces::Contract, account_id: &near_workspaces::AccountId) -> anyhow::Result<u128> {
+    let balance = ft_contract
+        .call("ft_balance_of")
+        .args_json(json!({"account_id": account_id}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    return Ok(balance.0);
+}
+
+async fn get_unbonds(core_contract: &near_workspaces::Contract, account: &near_workspaces::Account, vapi_id: &str) -> anyhow::Result<Vec<Value>> {
+    let unbonds = core_contract
+        .call("get_unbonds")
+        .args_json(json!({"account_id": account.id(), "vapi_id": vapi_id}))
+        .view()
+        .await?
+        .json::<Vec<Value>>()?;
+    return Ok(unbonds);
+}
@@ -1,9 +1,14 @@
-use near_sdk::{json_types::U128, NearToken};
+use near_sdk::{json_types::{U128, U64}, NearToken};
 use serde_json::json;
 
 use crate::common::utils::*;
 pub mod common;
 
+// 정산 기간 (block timestamp, ns)
+const JANUARY: u64 = 1_704_067_200_000_000_000;
+const FEBRUARY: u64 = 1_706_745_600_000_000_000;
+const MARCH: u64 = 1_709_251_200_000_000_000;
+
 #[tokio::test]
 async fn test_vapi() -> anyhow::Result<()> {
     let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
@@ -27,6 +32,8 @@ async fn test_vapi() -> anyhow::Result<()> {
             .transact()
             .await?;
         assert!(res.is_success());
+
+        deposit_storage(&core_contract, user, NearToken::from_near(1)).await?;
     }
 
     let alice = users.get(0).unwrap().clone();
@@ -41,12 +48,21 @@ async fn test_vapi() -> anyhow::Result<()> {
         .await?;
     assert!(res.is_success());
 
+    // 정산된 delegator 몫이 바로 풀리도록 지급 기간을 없앤다.
+    let res = owner
+        .call(core_contract.id(), "set_reward_release_period")
+        .args_json(json!({"vapi_id": vapi_id, "reward_release_period": U64(0)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
     let transfer_balance = U128::from(NearToken::from_near(10).as_yoctonear());
 
     // alice가 10토큰을 VAPI에 입금
     let res = alice
         .call(ft_contract.id(), "ft_transfer_call")
-        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "vapi_id": vapi_id }).to_string()))
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": vapi_id }).to_string()))
         .max_gas()
         .deposit(ONE_YOCTO)
         .transact()
@@ -58,7 +74,7 @@ async fn test_vapi() -> anyhow::Result<()> {
     // bob이 10토큰을 VAPI에 입금
     let res = bob
         .call(ft_contract.id(), "ft_transfer_call")
-        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "vapi_id": vapi_id }).to_string()))
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": vapi_id }).to_string()))
         .max_gas()
         .deposit(ONE_YOCTO)
         .transact()
@@ -67,10 +83,17 @@ async fn test_vapi() -> anyhow::Result<()> {
     res.logs().iter().for_each(|log| println!("{:?}", log));
     assert!(res.is_success());
 
-    // 10토큰 정산
+    // 10토큰 정산 (2024년 1월분)
     let res = owner
         .call(ft_contract.id(), "ft_transfer_call")
-        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "vapi_ids": vec![vapi_id], "amounts": vec![transfer_balance] }).to_string()))
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({
+            "action": "settlement",
+            "batch_id": "2024-01",
+            "period_start": U64(JANUARY),
+            "period_end": U64(FEBRUARY),
+            "vapi_ids": vec![vapi_id],
+            "amounts": vec![transfer_balance]
+        }).to_string()))
         .max_gas()
         .deposit(ONE_YOCTO)
         .transact()
@@ -110,7 +133,7 @@ async fn test_vapi() -> anyhow::Result<()> {
     println!("alice_origin_balance: {}", alice_origin_balance);
     
     let res = alice.call(ft_contract.id(), "ft_transfer_call")
-        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "vapi_id": vapi_id }).to_string()))
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": vapi_id }).to_string()))
         .max_gas()
         .deposit(ONE_YOCTO)
         .transact()
@@ -127,10 +150,17 @@ async fn test_vapi() -> anyhow::Result<()> {
     // alice의 원금에서 추가로 입금한 10토큰을 빼고 클레임한 1.95토큰을 더한 금액
     assert_eq!(alice_after_balance, alice_origin_balance - transfer_balance.0 + 19_500_000_000_000_000_000_000_00);
 
-    // 10토큰 정산
+    // 10토큰 정산 (2024년 2월분)
     let res = owner
         .call(ft_contract.id(), "ft_transfer_call")
-        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "vapi_ids": vec![vapi_id], "amounts": vec![transfer_balance] }).to_string()))
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({
+            "action": "settlement",
+            "batch_id": "2024-02",
+            "period_start": U64(FEBRUARY),
+            "period_end": U64(MARCH),
+            "vapi_ids": vec![vapi_id],
+            "amounts": vec![transfer_balance]
+        }).to_string()))
         .max_gas()
         .deposit(ONE_YOCTO)
         .transact()
@@ -157,5 +187,83 @@ async fn test_vapi() -> anyhow::Result<()> {
         .json::<u128>()?;
     assert_eq!(bob_reward, 32_500_000_000_000_000_000_000_00);
 
+    // 보상을 100초에 걸쳐 선형으로 지급하는 VAPI에서는 정산 직전에 입금한 계정이 같은 몫을 가져가지 못한다.
+    let release_vapi_id = "release-vapi";
+    let res = alice
+        .call(core_contract.id(), "create_vapi")
+        .args_json(json!({"vapi_id": release_vapi_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = owner
+        .call(core_contract.id(), "set_reward_release_period")
+        .args_json(json!({"vapi_id": release_vapi_id, "reward_release_period": U64(100 * 1_000_000_000)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // alice가 10토큰을 입금한 뒤 10토큰이 정산된다.
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": release_vapi_id }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({
+            "action": "settlement",
+            "batch_id": "2024-02-release",
+            "period_start": U64(FEBRUARY),
+            "period_end": U64(MARCH),
+            "vapi_ids": vec![release_vapi_id],
+            "amounts": vec![transfer_balance]
+        }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 지급 기간의 절반쯤 지났을 때 bob이 같은 금액을 입금한다.
+    worker.fast_forward(50).await?;
+    let res = bob
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((core_contract.id(), transfer_balance, Option::<String>::None, serde_json::json!({ "action": "deposit", "vapi_id": release_vapi_id }).to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 지급 기간이 끝나면 3.9토큰이 모두 풀리지만 bob은 입금한 이후의 몫만 받는다.
+    worker.fast_forward(200).await?;
+    let alice_reward = core_contract
+        .call("pending_reward")
+        .args_json((alice.id(), release_vapi_id))
+        .view()
+        .await?
+        .json::<u128>()?;
+    let bob_reward = core_contract
+        .call("pending_reward")
+        .args_json((bob.id(), release_vapi_id))
+        .view()
+        .await?
+        .json::<u128>()?;
+    println!("alice_reward: {}, bob_reward: {}", alice_reward, bob_reward);
+
+    let delegator_amount: u128 = 39_000_000_000_000_000_000_000_00;
+    assert!(bob_reward > 0);
+    assert!(bob_reward < delegator_amount / 2);
+    assert!(alice_reward > delegator_amount / 2);
+    assert!(alice_reward + bob_reward <= delegator_amount);
+    assert!(alice_reward + bob_reward > delegator_amount - delegator_amount / 1_000_000);
+
     return Ok(());
 }
\ No newline at end of file
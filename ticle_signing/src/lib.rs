@@ -0,0 +1,138 @@
+//! Off-chain signed payloads shared by `TicleCore` and the token: how they are encoded
+//! and how their signatures are checked.
+//!
+//! Every payload is signed as `hash(tag, version, payload)`, where `tag` tells the kinds
+//! of payload apart and `version` is bumped whenever a payload's layout changes.
+//!
+//! The `dalek` feature checks signatures with `ed25519-dalek` inside the contract, the way
+//! `TicleCore` did before `env::ed25519_verify`. It only exists so the gas simulation can
+//! compare the two; never deploy a build with it.
+
+// 다른 컨트랙트 코드와 같이 명시적인 return을 쓴다.
+#![allow(clippy::needless_return)]
+
+use near_sdk::borsh::{self, BorshSerialize};
+use near_sdk::{bs58, env, log, CryptoHash, PublicKey};
+
+/// Base of every domain separation tag, in the spirit of NEP-413. Tags sit in the
+/// off-chain message range (`>= 2^31`) above any NEP number, so a signed payload can
+/// never be a valid signature of a transaction, a NEP-413 message or another kind of
+/// payload signed with the same key. Each kind of payload adds its own offset.
+pub const TAG_BASE: u32 = (1 << 31) + (1 << 30);
+
+/// `borsh(tag) || version || borsh(payload)`
+pub fn signing_bytes(tag: u32, version: u8, payload: &impl BorshSerialize) -> Vec<u8> {
+    let mut bytes = tag.to_le_bytes().to_vec();
+    bytes.push(version);
+    bytes.extend(borsh::to_vec(payload).expect("Failed to serialize signed payload"));
+    return bytes;
+}
+
+/// SHA-256 of `signing_bytes`. This is the message that gets signed.
+pub fn signing_hash(tag: u32, version: u8, payload: &impl BorshSerialize) -> CryptoHash {
+    return env::sha256_array(&signing_bytes(tag, version, payload));
+}
+
+/// Decodes an `ed25519:<base58>` signature. The prefix is optional.
+pub fn decode_signature(signature: &str) -> Option<[u8; 64]> {
+    let signature_base58 = signature.trim_start_matches("ed25519:");
+
+    // Decode the base58 signature
+    let signature_bytes = match bs58::decode(signature_base58).into_vec() {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            log!("[decode_signature] Invalid base58 in signature");
+            return None;
+        }
+    };
+
+    return match signature_bytes.try_into() {
+        Ok(signature) => Some(signature),
+        Err(_) => {
+            log!("[decode_signature] Invalid signature");
+            None
+        }
+    };
+}
+
+#[cfg(not(feature = "dalek"))]
+pub fn verify_ed25519(signature: &[u8; 64], message: &[u8], public_key: &PublicKey) -> bool {
+    // near_sdk::PublicKey는 맨 앞 1바이트가 curve type이다.
+    let ed25519_public_key: [u8; 32] = match public_key.as_bytes()[1..].try_into() {
+        Ok(pk) => pk,
+        Err(_) => {
+            log!("[verify_ed25519] Invalid public key");
+            return false;
+        }
+    };
+
+    return env::ed25519_verify(signature, message, &ed25519_public_key);
+}
+
+#[cfg(feature = "dalek")]
+pub fn verify_ed25519(signature: &[u8; 64], message: &[u8], public_key: &PublicKey) -> bool {
+    use ed25519_dalek::Verifier;
+
+    let ed25519_public_key = match ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..]) {
+        Ok(pk) => pk,
+        Err(_) => {
+            log!("[verify_ed25519] Invalid public key");
+            return false;
+        }
+    };
+    let signature = match ed25519_dalek::Signature::from_bytes(signature) {
+        Ok(signature) => signature,
+        Err(_) => {
+            log!("[verify_ed25519] Invalid signature");
+            return false;
+        }
+    };
+
+    return ed25519_public_key.verify(message, &signature).is_ok();
+}
+
+/// Checks an `ed25519:<base58>` signature of `message` against `public_key`.
+pub fn verify_signature(message: &[u8], signature: &str, public_key: &PublicKey) -> bool {
+    return decode_signature(signature).is_some_and(|signature| verify_ed25519(&signature, message, public_key));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::CurveType;
+
+    const TAG: u32 = TAG_BASE + 9;
+
+    #[derive(BorshSerialize)]
+    #[borsh(crate = "near_sdk::borsh")]
+    struct Payload {
+        nonce: u64,
+    }
+
+    #[test]
+    fn signing_bytes_start_with_tag_and_version() {
+        let payload = Payload { nonce: 7 };
+        let bytes = signing_bytes(TAG, 3, &payload);
+        assert_eq!(&bytes[..4], &TAG.to_le_bytes());
+        assert_eq!(bytes[4], 3);
+        assert_eq!(&bytes[5..], borsh::to_vec(&payload).unwrap().as_slice());
+    }
+
+    #[test]
+    fn hash_commits_to_tag_and_version() {
+        let payload = Payload { nonce: 7 };
+        let original = signing_hash(TAG, 1, &payload);
+        assert_ne!(original, signing_hash(TAG + 1, 1, &payload));
+        assert_ne!(original, signing_hash(TAG, 2, &payload));
+        assert_ne!(original, signing_hash(TAG, 1, &Payload { nonce: 8 }));
+    }
+
+    #[test]
+    fn malformed_signatures_are_rejected() {
+        let public_key = PublicKey::from_parts(CurveType::ED25519, vec![1; 32]).unwrap();
+        assert!(decode_signature("ed25519:0OIl").is_none());
+        assert!(decode_signature(&bs58::encode([1u8; 63]).into_string()).is_none());
+        assert!(!verify_signature(b"message", "ed25519:0OIl", &public_key));
+        assert!(!verify_signature(b"message", &bs58::encode([1u8; 64]).into_string(), &public_key));
+    }
+}
@@ -0,0 +1,150 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::collections::UnorderedSet;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::token::{StorageKey, TokenContract, TokenContractExt};
+
+/// Permissions the owner can hand out. The owner itself holds none of them implicitly,
+/// except that it may always update metadata.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    /// May call `mint`.
+    Minter,
+    /// May pause and unpause transfers.
+    Pauser,
+    /// May update the icon and reference of the token metadata.
+    MetadataAdmin,
+}
+
+#[near]
+impl TokenContract {
+    /// Starts handing the contract over to `new_owner_id`, who has to call
+    /// `accept_ownership`. `None` cancels a pending transfer.
+    #[payable]
+    pub fn transfer_ownership(&mut self, new_owner_id: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Pending owner: {:?}", new_owner_id);
+        self.pending_owner_id = new_owner_id;
+    }
+
+    #[payable]
+    pub fn accept_ownership(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        require!(self.pending_owner_id.as_ref() == Some(&account_id), "Only pending owner can accept ownership");
+
+        log!("Ownership transferred from @{} to @{}", self.owner_id, account_id);
+        self.owner_id = account_id;
+        self.pending_owner_id = None;
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        return self.owner_id.clone();
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        return self.pending_owner_id.clone();
+    }
+
+    #[payable]
+    pub fn grant_role(&mut self, role: Role, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut members = self.roles.get(&role).unwrap_or_else(|| UnorderedSet::new(StorageKey::RoleMembers { role }));
+        require!(members.insert(&account_id), "Account already has the role");
+        self.roles.insert(&role, &members);
+        log!("Granted {:?} to @{}", role, account_id);
+    }
+
+    #[payable]
+    pub fn revoke_role(&mut self, role: Role, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut members = self.roles.get(&role).expect("Account doesn't have the role");
+        require!(members.remove(&account_id), "Account doesn't have the role");
+        self.roles.insert(&role, &members);
+        log!("Revoked {:?} from @{}", role, account_id);
+    }
+
+    pub fn has_role(&self, role: Role, account_id: AccountId) -> bool {
+        return self.internal_has_role(role, &account_id);
+    }
+
+    pub fn get_role_members(&self, role: Role) -> Vec<AccountId> {
+        return self.roles.get(&role).map_or(Vec::new(), |members| members.to_vec());
+    }
+
+    /// Stops `ft_transfer`, `ft_transfer_call` and burns. `ft_resolve_transfer` keeps
+    /// working, so transfers already in flight still get their refunds.
+    #[payable]
+    pub fn pause(&mut self) {
+        assert_one_yocto();
+        self.assert_role(Role::Pauser, "Only pauser can pause");
+        require!(!self.paused, "Already paused");
+        self.paused = true;
+        log!("Paused by @{}", env::predecessor_account_id());
+    }
+
+    #[payable]
+    pub fn unpause(&mut self) {
+        assert_one_yocto();
+        self.assert_role(Role::Pauser, "Only pauser can unpause");
+        require!(self.paused, "Not paused");
+        self.paused = false;
+        log!("Unpaused by @{}", env::predecessor_account_id());
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.paused;
+    }
+
+    #[payable]
+    pub fn set_icon(&mut self, icon: Option<String>) {
+        self.update_metadata(|metadata| metadata.icon = icon);
+    }
+
+    /// `reference_hash` is the base64 SHA-256 of the JSON file at `reference`.
+    #[payable]
+    pub fn set_reference(&mut self, reference: Option<String>, reference_hash: Option<Base64VecU8>) {
+        self.update_metadata(|metadata| {
+            metadata.reference = reference;
+            metadata.reference_hash = reference_hash;
+        });
+    }
+}
+
+impl TokenContract {
+    pub(crate) fn assert_owner(&self) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only owner can call this method");
+    }
+
+    pub(crate) fn assert_not_paused(&self) {
+        require!(!self.paused, "Token is paused");
+    }
+
+    pub(crate) fn assert_role(&self, role: Role, message: &str) {
+        require!(self.internal_has_role(role, &env::predecessor_account_id()), message);
+    }
+
+    fn internal_has_role(&self, role: Role, account_id: &AccountId) -> bool {
+        return self.roles.get(&role).is_some_and(|members| members.contains(account_id));
+    }
+
+    fn update_metadata(&mut self, update: impl FnOnce(&mut FungibleTokenMetadata)) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        require!(
+            account_id == self.owner_id || self.internal_has_role(Role::MetadataAdmin, &account_id),
+            "Only owner or metadata admin can update metadata"
+        );
+
+        let mut metadata = self.metadata.get().unwrap();
+        update(&mut metadata);
+        metadata.assert_valid();
+        self.metadata.set(&metadata);
+        log!("Metadata updated by @{}", account_id);
+    }
+}
@@ -0,0 +1,57 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, NearToken};
+
+use crate::token::{refund_storage_deposit, TokenContract, TokenContractExt};
+
+#[near]
+impl TokenContract {
+    /// Lets `spender_id` move up to `amount` of the caller's tokens with
+    /// `ft_transfer_from`. Replaces the previous allowance; 0 revokes it. The attached
+    /// deposit pays for storage and the rest is refunded.
+    #[payable]
+    pub fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        require!(env::attached_deposit() >= NearToken::from_yoctonear(1), "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        self.internal_approve(&env::predecessor_account_id(), &spender_id, amount.into());
+        refund_storage_deposit(initial_storage_usage);
+    }
+
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        return self.allowances.get(&(owner_id, spender_id)).unwrap_or(0).into();
+    }
+
+    /// Moves `amount` of `owner_id`'s tokens to `receiver_id` out of the allowance
+    /// `owner_id` gave the caller.
+    #[payable]
+    pub fn ft_transfer_from(&mut self, owner_id: AccountId, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused();
+        let spender_id = env::predecessor_account_id();
+        let amount: Balance = amount.into();
+        let key = (owner_id.clone(), spender_id);
+        let allowance = self.allowances.get(&key).unwrap_or(0);
+        require!(amount <= allowance, "Transfer amount exceeds allowance");
+
+        if allowance == amount {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &(allowance - amount));
+        }
+        self.token.internal_transfer(&owner_id, &receiver_id, amount, memo);
+    }
+}
+
+impl TokenContract {
+    pub(crate) fn internal_approve(&mut self, owner_id: &AccountId, spender_id: &AccountId, amount: Balance) {
+        require!(owner_id != spender_id, "Can't approve yourself");
+
+        let key = (owner_id.clone(), spender_id.clone());
+        if amount == 0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &amount);
+        }
+        log!("Account @{} allowed @{} to transfer {}", owner_id, spender_id, amount);
+    }
+}
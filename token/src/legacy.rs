@@ -0,0 +1,13 @@
+//! State layout of the first token release, which stored only the balances and the
+//! metadata. Only used by `TokenContract::migrate`.
+
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_contract_standards::fungible_token::FungibleToken;
+use near_sdk::collections::LazyOption;
+use near_sdk::near;
+
+#[near(serializers = [borsh])]
+pub struct LegacyTokenContract {
+    pub token: FungibleToken,
+    pub metadata: LazyOption<FungibleTokenMetadata>,
+}
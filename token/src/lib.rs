@@ -1,11 +1,48 @@
+pub mod admin;
+pub mod allowance;
+pub mod legacy;
+pub mod permit;
 pub mod token;
+pub mod vesting;
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use near_contract_standards::fungible_token::metadata::{FungibleTokenMetadata, FT_METADATA_SPEC};
-    use near_sdk::test_utils::accounts;
+    use admin::Role;
+    use near_contract_standards::fungible_token::metadata::{FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC};
+    use near_contract_standards::fungible_token::{FungibleToken, FungibleTokenCore, FungibleTokenResolver};
+    use near_sdk::collections::LazyOption;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{env, testing_env, NearToken, PromiseResult};
+
+    fn metadata() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "T Token".to_string(),
+            symbol: "TIC".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        }
+    }
+
+    fn set_caller(predecessor: usize, signer: usize, deposit: NearToken) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(predecessor))
+            .signer_account_id(accounts(signer))
+            .attached_deposit(deposit)
+            .build());
+    }
+
+    /// A token deployed by `accounts(0)` whose whole `total_supply` belongs to its owner
+    /// `accounts(1)`, capped at 1,000.
+    fn new_contract(total_supply: u128) -> token::TokenContract {
+        set_caller(0, 0, NearToken::from_yoctonear(0));
+        return token::TokenContract::new(accounts(1), total_supply.into(), metadata(), 1_000.into());
+    }
 
     #[test]
     fn test_token() {
@@ -20,7 +57,291 @@ mod tests {
             reference_hash: None,
             decimals: 24,
         };
-        let contract = token::TokenContract::new(accounts(1).into(), total_supply, metadata);
+        let contract = token::TokenContract::new(accounts(1).into(), total_supply, metadata, 1_000.into());
         assert_eq!(contract.metadata.get().unwrap().name, "Binance");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn burn_uses_predecessor_balance() {
+        let mut contract = new_contract(100);
+        contract.token.internal_register_account(&accounts(2));
+        contract.token.internal_transfer(&accounts(1), &accounts(2), 40, None);
+
+        // accounts(1)이 서명한 트랜잭션에서 accounts(2)(컨트랙트)가 호출해도 accounts(2)의 토큰이 소각된다.
+        set_caller(2, 1, NearToken::from_yoctonear(1));
+        contract.burn(10.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 60);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 30);
+        assert_eq!(contract.ft_total_supply().0, 90);
+    }
+
+    #[test]
+    fn burn_from_spends_allowance() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_millinear(10));
+        contract.approve_burn(accounts(2), 30.into());
+        assert_eq!(contract.burn_allowance(accounts(1), accounts(2)).0, 30);
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.burn_from(accounts(1), 20.into(), Some("fees".to_string()));
+        assert_eq!(contract.burn_allowance(accounts(1), accounts(2)).0, 10);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 80);
+        assert_eq!(contract.ft_total_supply().0, 80);
+    }
+
+    #[test]
+    #[should_panic(expected = "Burn amount exceeds allowance")]
+    fn burn_from_over_allowance() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_millinear(10));
+        contract.approve_burn(accounts(2), 30.into());
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.burn_from(accounts(1), 31.into(), None);
+    }
+
+    #[test]
+    fn mint_up_to_max_supply() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.grant_role(Role::Minter, accounts(2));
+        assert!(contract.has_role(Role::Minter, accounts(2)));
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.mint(accounts(1), 900.into(), Some("emissions".to_string()));
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mint would exceed max_supply")]
+    fn mint_over_max_supply() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.grant_role(Role::Minter, accounts(2));
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.mint(accounts(1), 901.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only minter can mint")]
+    fn mint_requires_minter() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.mint(accounts(1), 1.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn grant_role_requires_one_yocto() {
+        let mut contract = new_contract(100);
+
+        // function call access key로는 1 yoctoNEAR를 붙일 수 없다.
+        set_caller(1, 1, NearToken::from_yoctonear(0));
+        contract.grant_role(Role::Minter, accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn pause_requires_one_yocto() {
+        let mut contract = new_contract(100);
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.grant_role(Role::Pauser, accounts(2));
+
+        set_caller(2, 2, NearToken::from_yoctonear(0));
+        contract.pause();
+    }
+
+    #[test]
+    fn ownership_transfer_needs_acceptance() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.transfer_ownership(Some(accounts(2)));
+        assert_eq!(contract.get_owner(), accounts(1));
+        assert_eq!(contract.get_pending_owner(), Some(accounts(2)));
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), accounts(2));
+        assert_eq!(contract.get_pending_owner(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only pending owner can accept ownership")]
+    fn ownership_cannot_be_taken() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.transfer_ownership(Some(accounts(2)));
+
+        set_caller(3, 3, NearToken::from_yoctonear(1));
+        contract.accept_ownership();
+    }
+
+    #[test]
+    fn metadata_admin_updates_metadata() {
+        let mut contract = new_contract(100);
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.grant_role(Role::MetadataAdmin, accounts(2));
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.set_icon(Some("data:image/svg+xml,icon".to_string()));
+        contract.set_reference(Some("https://example.com/tic.json".to_string()), Some(vec![0; 32].into()));
+
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.icon.as_deref(), Some("data:image/svg+xml,icon"));
+        assert_eq!(metadata.reference.as_deref(), Some("https://example.com/tic.json"));
+        assert_eq!(metadata.name, "T Token");
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner or metadata admin can update metadata")]
+    fn metadata_update_requires_role() {
+        let mut contract = new_contract(100);
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.set_icon(None);
+    }
+
+    #[test]
+    fn vesting_claim_and_revoke() {
+        let mut contract = new_contract(1_000);
+        contract.token.internal_register_account(&accounts(2));
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        let vesting_id = contract.create_vesting(accounts(2), 400.into(), 100.into(), 100.into(), 400.into(), true);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 600);
+
+        // 절반이 지난 시점: 200이 풀렸다.
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(300)
+            .build());
+        assert_eq!(contract.claim_vesting(vesting_id).0, 200);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 200);
+
+        // 3/4 시점에 회수하면 남은 100만 돌려받는다.
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(400)
+            .build());
+        assert_eq!(contract.revoke_vesting(vesting_id).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 700);
+
+        let vesting = contract.get_vestings(accounts(2), None, None).pop().unwrap();
+        let vesting = near_sdk::serde_json::to_value(vesting).unwrap();
+        assert_eq!(vesting["claimable_amount"], "100");
+        assert_eq!(vesting["unvested_amount"], "0");
+    }
+
+    #[test]
+    fn transfer_from_spends_allowance() {
+        let mut contract = new_contract(100);
+        contract.token.internal_register_account(&accounts(3));
+
+        set_caller(1, 1, NearToken::from_millinear(10));
+        contract.ft_approve(accounts(2), 30.into());
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(2)).0, 30);
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.ft_transfer_from(accounts(1), accounts(3), 20.into(), None);
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(2)).0, 10);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 80);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 20);
+
+        // 0으로 승인하면 남은 allowance가 사라진다.
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.ft_approve(accounts(2), 0.into());
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(2)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer amount exceeds allowance")]
+    fn transfer_from_over_allowance() {
+        let mut contract = new_contract(100);
+        contract.token.internal_register_account(&accounts(3));
+
+        set_caller(1, 1, NearToken::from_millinear(10));
+        contract.ft_approve(accounts(2), 30.into());
+
+        set_caller(2, 2, NearToken::from_yoctonear(1));
+        contract.ft_transfer_from(accounts(1), accounts(3), 31.into(), None);
+    }
+
+    #[test]
+    fn in_flight_transfer_call_is_refunded_while_paused() {
+        let mut contract = new_contract(100);
+        contract.token.internal_register_account(&accounts(2));
+
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.ft_transfer_call(accounts(2), 40.into(), None, "".to_string());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 40);
+
+        // ft_on_transfer가 실행되는 사이에 정지되고, 받는 쪽은 40을 모두 돌려준다.
+        contract.paused = true;
+        testing_env!(
+            VMContextBuilder::new().predecessor_account_id(accounts(0)).current_account_id(accounts(0)).build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128(40)).unwrap())]
+        );
+        let used_amount = contract.ft_resolve_transfer(accounts(1), accounts(2), 40.into());
+        assert_eq!(used_amount.0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+    }
+
+    /// Writes the state the first release left behind, as the contract account.
+    fn write_first_release_state(balances: &[(usize, u128)]) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        // 첫 배포 버전의 StorageKey는 FungibleToken = 0, Metadata = 1이었다.
+        let mut legacy_token = FungibleToken::new(vec![0u8]);
+        for (account, balance) in balances {
+            legacy_token.internal_register_account(&accounts(*account));
+            legacy_token.internal_deposit(&accounts(*account), *balance);
+        }
+        env::state_write(&legacy::LegacyTokenContract {
+            token: legacy_token,
+            metadata: LazyOption::new(vec![1u8], Some(&metadata())),
+        });
+    }
+
+    #[test]
+    fn migrate_from_first_release_state() {
+        write_first_release_state(&[(1, 100), (2, 50)]);
+        let mut contract = token::TokenContract::migrate(accounts(1), 1_000.into());
+        assert_eq!(contract.ft_total_supply().0, 150);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 50);
+        assert_eq!(contract.ft_metadata().symbol, "TIC");
+        assert_eq!(contract.get_owner(), accounts(1));
+        assert_eq!(contract.ft_max_supply().0, 1_000);
+
+        // 이후 요청에서 추가된 기능도 바로 쓸 수 있다.
+        set_caller(1, 1, NearToken::from_yoctonear(1));
+        contract.grant_role(Role::Minter, accounts(1));
+        contract.mint(accounts(2), 850.into(), None);
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "total_supply can't exceed max_supply")]
+    fn migrate_rejects_cap_below_supply() {
+        write_first_release_state(&[(1, 100)]);
+        token::TokenContract::migrate(accounts(1), 99.into());
+    }
+}
@@ -0,0 +1,142 @@
+//! Signed permits: an owner signs an allowance off-chain and a relayer submits it.
+//!
+//! Wallets and tests should build a `PermitPayload` and sign `hash()` with a key the owner
+//! registered with `add_permit_key`, instead of re-implementing the encoding.
+
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, CryptoHash, CurveType, NearToken, PublicKey};
+use ticle_signing::{signing_bytes, signing_hash, verify_signature, TAG_BASE};
+
+use crate::token::{refund_storage_deposit, TokenContract, TokenContractExt};
+
+/// Domain separation tag of permits. See `ticle_signing::TAG_BASE`.
+pub const PERMIT_TAG: u32 = TAG_BASE + 3;
+
+/// Bump together with the `PermitPayload` layout.
+pub const PERMIT_PAYLOAD_VERSION: u8 = 1;
+
+/// Everything a permit signature commits to.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PermitPayload {
+    /// Token account the permit is meant for.
+    pub contract_id: AccountId,
+    pub owner_id: AccountId,
+    pub spender_id: AccountId,
+    pub amount: u128,
+    /// Must equal `permit_nonce(owner_id)`, so every permit can be used once and in order.
+    pub nonce: u64,
+    /// Block timestamp in nanoseconds after which the permit is rejected.
+    pub deadline: u64,
+}
+
+impl PermitPayload {
+    /// `borsh(PERMIT_TAG) || PERMIT_PAYLOAD_VERSION || borsh(self)`
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        return signing_bytes(PERMIT_TAG, PERMIT_PAYLOAD_VERSION, self);
+    }
+
+    /// SHA-256 of `signing_bytes()`. This is the message that gets signed.
+    pub fn hash(&self) -> CryptoHash {
+        return signing_hash(PERMIT_TAG, PERMIT_PAYLOAD_VERSION, self);
+    }
+}
+
+/// The signed fields of a permit as submitted by the relayer. The contract account is
+/// added when the payload is rebuilt, so it isn't part of the arguments.
+#[near(serializers = [json])]
+pub struct Permit {
+    pub owner_id: AccountId,
+    pub spender_id: AccountId,
+    pub amount: U128,
+    pub nonce: U64,
+    pub deadline: U64,
+}
+
+#[near]
+impl TokenContract {
+    /// Registers a key that may sign permits for the caller. The attached deposit pays
+    /// for storage and the rest is refunded.
+    #[payable]
+    pub fn add_permit_key(&mut self, public_key: PublicKey) {
+        require!(env::attached_deposit() >= NearToken::from_yoctonear(1), "Requires attached deposit of at least 1 yoctoNEAR");
+        require!(public_key.curve_type() == CurveType::ED25519, "Only ed25519 permit keys are supported");
+        let initial_storage_usage = env::storage_usage();
+        let owner_id = env::predecessor_account_id();
+        require!(self.permit_keys.insert(&(owner_id.clone(), public_key)), "Permit key already exists");
+        log!("Account @{} added a permit key", owner_id);
+        refund_storage_deposit(initial_storage_usage);
+    }
+
+    #[payable]
+    pub fn remove_permit_key(&mut self, public_key: PublicKey) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        require!(self.permit_keys.remove(&(owner_id.clone(), public_key)), "Permit key not found");
+        log!("Account @{} removed a permit key", owner_id);
+    }
+
+    /// Sets the allowance `permit.owner_id` signed for `permit.spender_id`. Anyone may
+    /// submit it; the attached deposit pays for storage of the allowance.
+    #[payable]
+    pub fn permit(&mut self, permit: Permit, public_key: PublicKey, signature: String) {
+        let owner_id = permit.owner_id;
+        require!(env::block_timestamp() <= permit.deadline.0, "Permit expired");
+        require!(self.permit_keys.contains(&(owner_id.clone(), public_key.clone())), "Permit key not found");
+        let expected_nonce = self.permit_nonces.get(&owner_id).unwrap_or(0);
+        require!(permit.nonce.0 == expected_nonce, format!("Invalid nonce, expected {}", expected_nonce));
+
+        let payload = PermitPayload {
+            contract_id: env::current_account_id(),
+            owner_id: owner_id.clone(),
+            spender_id: permit.spender_id.clone(),
+            amount: permit.amount.0,
+            nonce: permit.nonce.0,
+            deadline: permit.deadline.0,
+        };
+        require!(verify_signature(&payload.hash(), &signature, &public_key), "Invalid signature");
+
+        let initial_storage_usage = env::storage_usage();
+        self.permit_nonces.insert(&owner_id, &(expected_nonce + 1));
+        self.internal_approve(&owner_id, &permit.spender_id, permit.amount.0);
+        refund_storage_deposit(initial_storage_usage);
+    }
+
+    /// Nonce the next permit of `owner_id` has to use.
+    pub fn permit_nonce(&self, owner_id: AccountId) -> U64 {
+        return U64(self.permit_nonces.get(&owner_id).unwrap_or(0));
+    }
+
+    pub fn has_permit_key(&self, owner_id: AccountId, public_key: PublicKey) -> bool {
+        return self.permit_keys.contains(&(owner_id, public_key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> PermitPayload {
+        PermitPayload {
+            contract_id: "token.near".parse().unwrap(),
+            owner_id: "alice.near".parse().unwrap(),
+            spender_id: "core.near".parse().unwrap(),
+            amount: 100,
+            nonce: 0,
+            deadline: 1_000,
+        }
+    }
+
+    #[test]
+    fn hash_commits_to_nonce_and_spender() {
+        let original = payload().hash();
+
+        let mut other = payload();
+        other.nonce += 1;
+        assert_ne!(original, other.hash());
+
+        let mut other = payload();
+        other.spender_id = "mallory.near".parse().unwrap();
+        assert_ne!(original, other.hash());
+    }
+}
@@ -1,15 +1,29 @@
-use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
-use near_contract_standards::fungible_token::{FungibleToken, FungibleTokenCore, FungibleTokenResolver};
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
+use near_contract_standards::fungible_token::metadata::{FungibleTokenMetadata, FungibleTokenMetadataProvider};
+use near_contract_standards::fungible_token::{Balance, FungibleToken, FungibleTokenCore, FungibleTokenResolver};
 use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
-use near_sdk::collections::LazyOption;
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
+
+use crate::admin::Role;
+use crate::legacy::LegacyTokenContract;
+use crate::vesting::VestingSchedule;
 use near_sdk::json_types::U128;
-use near_sdk::{env, log, near, AccountId, BorshStorageKey, NearToken, PanicOnDefault, PromiseOrValue};
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, BorshStorageKey, CryptoHash, NearToken, PanicOnDefault, Promise, PromiseOrValue, PublicKey};
 
 #[derive(BorshStorageKey)]
 #[near]
-enum StorageKey {
+pub(crate) enum StorageKey {
     FungibleToken,
     Metadata,
+    BurnAllowances,
+    Roles,
+    RoleMembers { role: Role },
+    Vestings,
+    Allowances,
+    PermitKeys,
+    PermitNonces,
+    VestingsPerBeneficiary,
+    BeneficiaryVestings { account_hash: CryptoHash },
 }
 
 #[near(contract_state)]
@@ -17,24 +31,49 @@ enum StorageKey {
 pub struct TokenContract {
     pub token: FungibleToken,
     pub metadata: LazyOption<FungibleTokenMetadata>,
+    // (token owner, burner) -> amount the burner may still burn with `burn_from`
+    pub burn_allowances: LookupMap<(AccountId, AccountId), Balance>,
+    pub owner_id: AccountId,
+    // transfer_ownership으로 지정되어 accept_ownership을 기다리는 계정
+    pub pending_owner_id: Option<AccountId>,
+    // 역할별로 권한을 받은 계정 (minter: TicleCore, emissions 컨트랙트 등)
+    pub roles: LookupMap<Role, UnorderedSet<AccountId>>,
+    // 발행할 수 있는 총량의 상한. burn으로 줄어든 만큼은 다시 발행할 수 있다.
+    pub max_supply: Balance,
+    // 긴급 정지. ft_transfer, ft_transfer_call, burn이 막힌다.
+    pub paused: bool,
+    // 팀, 투자자, 생태계 물량의 vesting. 토큰은 컨트랙트 자신의 계정에 보관된다.
+    pub vestings: UnorderedMap<u64, VestingSchedule>,
+    pub next_vesting_id: u64,
+    // (token owner, spender) -> amount the spender may still move with `ft_transfer_from`
+    pub allowances: LookupMap<(AccountId, AccountId), Balance>,
+    // 계정별로 permit 서명에 쓸 수 있는 키
+    pub permit_keys: LookupSet<(AccountId, PublicKey)>,
+    // 계정별로 다음 permit이 사용해야 하는 nonce
+    pub permit_nonces: LookupMap<AccountId, u64>,
+    // 수혜자별 vesting id (생성 순서)
+    pub vestings_per_beneficiary: LookupMap<AccountId, Vector<u64>>,
 }
 
 #[near]
 impl TokenContract {
     #[init]
-    pub fn new(owner_id: AccountId, total_supply: U128, metadata: FungibleTokenMetadata) -> Self {
+    pub fn new(owner_id: AccountId, total_supply: U128, metadata: FungibleTokenMetadata, max_supply: U128) -> Self {
         assert!(!env::state_exists(), "Already exists");
         metadata.assert_valid();
+        require!(total_supply.0 <= max_supply.0, "total_supply can't exceed max_supply");
 
-        let mut this = Self {
-        token: FungibleToken::new(StorageKey::FungibleToken),
-        metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
-        };
+        let mut this = Self::with_state(
+            FungibleToken::new(StorageKey::FungibleToken),
+            LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            owner_id.clone(),
+            max_supply.0,
+        );
         
         this.token.internal_register_account(&owner_id);
         this.token.internal_deposit(&owner_id, total_supply.into());
         
-        near_contract_standards::fungible_token::events::FtMint{
+        FtMint{
         owner_id: &owner_id,
         amount: total_supply.into(),
         memo: Some("Minted {amount} tokens"),
@@ -43,9 +82,139 @@ impl TokenContract {
         return this;
     }
 
+    /// Upgrades state written by the first release, which kept only the balances and the
+    /// metadata. That release didn't store its owner, so `owner_id` is passed again
+    /// together with the supply cap.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: AccountId, max_supply: U128) -> Self {
+        let old_state: LegacyTokenContract = env::state_read().expect("Failed to read legacy state");
+        require!(old_state.token.total_supply <= max_supply.0, "total_supply can't exceed max_supply");
+        return Self::with_state(old_state.token, old_state.metadata, owner_id, max_supply.0);
+    }
+
+    /// Issues `amount` new tokens to `account_id`, which must be registered, as long as
+    /// the total supply stays within `max_supply`.
+    #[payable]
+    pub fn mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_role(Role::Minter, "Only minter can mint");
+        let amount: Balance = amount.into();
+        require!(amount > 0, "The amount should be a positive number");
+        let total_supply = self.token.total_supply.checked_add(amount).unwrap_or_else(|| env::panic_str("Total supply overflow"));
+        require!(total_supply <= self.max_supply, "Mint would exceed max_supply");
+
+        self.token.internal_deposit(&account_id, amount);
+
+        FtMint {
+            owner_id: &account_id,
+            amount: amount.into(),
+            memo: memo.as_deref(),
+        }.emit();
+    }
+
+    pub fn ft_max_supply(&self) -> U128 {
+        return self.max_supply.into();
+    }
+
+    /// Burns `amount` of the caller's own tokens. Contracts such as TicleCore call it
+    /// to burn tokens they hold, so it must act on the predecessor, not the signer.
     #[payable]
-    pub fn burn(&mut self, amount: U128) {
-        self.token.internal_withdraw(&env::signer_account_id(), amount.into());
+    pub fn burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused();
+        self.internal_burn(&env::predecessor_account_id(), amount.into(), memo);
+    }
+
+    /// Lets `burner_id` burn up to `amount` of the caller's tokens with `burn_from`.
+    /// Replaces the previous allowance; 0 revokes it. The attached deposit pays for
+    /// storage and the rest is refunded.
+    #[payable]
+    pub fn approve_burn(&mut self, burner_id: AccountId, amount: U128) {
+        require!(env::attached_deposit() >= NearToken::from_yoctonear(1), "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != burner_id, "Can't approve yourself");
+
+        let amount: Balance = amount.into();
+        if amount == 0 {
+            self.burn_allowances.remove(&(owner_id.clone(), burner_id.clone()));
+        } else {
+            self.burn_allowances.insert(&(owner_id.clone(), burner_id.clone()), &amount);
+        }
+        log!("Account @{} allowed @{} to burn {}", owner_id, burner_id, amount);
+
+        refund_storage_deposit(initial_storage_usage);
+    }
+
+    /// Burns `amount` of `owner_id`'s tokens out of the allowance `owner_id` gave the caller.
+    #[payable]
+    pub fn burn_from(&mut self, owner_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused();
+        let burner_id = env::predecessor_account_id();
+        let amount: Balance = amount.into();
+        let key = (owner_id.clone(), burner_id);
+        let allowance = self.burn_allowances.get(&key).unwrap_or(0);
+        require!(amount <= allowance, "Burn amount exceeds allowance");
+
+        if allowance == amount {
+            self.burn_allowances.remove(&key);
+        } else {
+            self.burn_allowances.insert(&key, &(allowance - amount));
+        }
+        self.internal_burn(&owner_id, amount, memo);
+    }
+
+    pub fn burn_allowance(&self, owner_id: AccountId, burner_id: AccountId) -> U128 {
+        return self.burn_allowances.get(&(owner_id, burner_id)).unwrap_or(0).into();
+    }
+}
+
+impl TokenContract {
+    /// Everything added after the first release starts out empty.
+    fn with_state(token: FungibleToken, metadata: LazyOption<FungibleTokenMetadata>, owner_id: AccountId, max_supply: Balance) -> Self {
+        return Self {
+            token,
+            metadata,
+            burn_allowances: LookupMap::new(StorageKey::BurnAllowances),
+            owner_id,
+            pending_owner_id: None,
+            roles: LookupMap::new(StorageKey::Roles),
+            max_supply,
+            paused: false,
+            vestings: UnorderedMap::new(StorageKey::Vestings),
+            next_vesting_id: 0,
+            allowances: LookupMap::new(StorageKey::Allowances),
+            permit_keys: LookupSet::new(StorageKey::PermitKeys),
+            permit_nonces: LookupMap::new(StorageKey::PermitNonces),
+            vestings_per_beneficiary: LookupMap::new(StorageKey::VestingsPerBeneficiary),
+        };
+    }
+
+    fn internal_burn(&mut self, account_id: &AccountId, amount: Balance, memo: Option<String>) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.token.internal_withdraw(account_id, amount);
+
+        FtBurn {
+            owner_id: account_id,
+            amount: amount.into(),
+            memo: memo.as_deref(),
+        }.emit();
+    }
+}
+
+/// Requires the attached deposit to cover the storage added since `initial_storage_usage`
+/// and refunds the rest to the caller.
+pub(crate) fn refund_storage_deposit(initial_storage_usage: u64) {
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    let required = env::storage_byte_cost().saturating_mul(storage_used as u128);
+    let attached = env::attached_deposit();
+    require!(attached >= required, format!("Must attach {} to cover storage", required));
+
+    let refund = attached.saturating_sub(required);
+    if refund > NearToken::from_yoctonear(1) {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
     }
 }
 
@@ -53,6 +222,7 @@ impl TokenContract {
 impl FungibleTokenCore for TokenContract {
     #[payable]
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        self.assert_not_paused();
         log!("receiver_id: {}", receiver_id);
         self.token.ft_transfer(receiver_id, amount, memo)
     }
@@ -65,6 +235,7 @@ impl FungibleTokenCore for TokenContract {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<U128> {
+        self.assert_not_paused();
         self.token.ft_transfer_call(receiver_id, amount, memo, msg)
     }
 
@@ -115,6 +286,9 @@ impl StorageManagement for TokenContract {
 
 #[near]
 impl FungibleTokenResolver for TokenContract {
+    /// Not gated by `assert_not_paused`: it only finishes an `ft_transfer_call` that was
+    /// accepted before the pause, and blocking it would leave the sender's refund with
+    /// the receiver.
     fn ft_resolve_transfer(
         &mut self,
         sender_id: AccountId,
@@ -127,4 +301,11 @@ impl FungibleTokenResolver for TokenContract {
         }
         used_amount.into()
     }
-}
\ No newline at end of file
+}
+
+#[near]
+impl FungibleTokenMetadataProvider for TokenContract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
@@ -0,0 +1,259 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::collections::Vector;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::token::{StorageKey, TokenContract, TokenContractExt};
+
+/// Tokens granted to `beneficiary_id`, held by the token contract's own account until
+/// they are claimed. Nothing vests before `cliff`; after it the grant vests linearly
+/// from `start` to `end`, so the part accrued before the cliff unlocks at once.
+/// Timestamps are block timestamps in nanoseconds.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingSchedule {
+    pub beneficiary_id: AccountId,
+    pub total_amount: Balance,
+    pub claimed_amount: Balance,
+    pub start: u64,
+    pub cliff: u64,
+    pub end: u64,
+    pub revocable: bool,
+    /// Once revoked, `total_amount` is cut down to what had vested.
+    pub revoked: bool,
+}
+
+impl VestingSchedule {
+    pub fn vested_amount(&self, now: u64) -> Balance {
+        if self.revoked || now >= self.end {
+            return self.total_amount;
+        }
+        if now < self.cliff {
+            return 0;
+        }
+        return mul_div(self.total_amount, now - self.start, self.end - self.start);
+    }
+
+    pub fn claimable_amount(&self, now: u64) -> Balance {
+        return self.vested_amount(now) - self.claimed_amount;
+    }
+}
+
+/// `amount * numerator / denominator` rounded down, for `numerator <= denominator`.
+/// `amount * numerator` alone overflows u128 for a 24-decimal grant vesting over
+/// months of nanoseconds, so the remainder is scaled separately.
+fn mul_div(amount: Balance, numerator: u64, denominator: u64) -> Balance {
+    let (numerator, denominator) = (numerator as u128, denominator as u128);
+    return amount / denominator * numerator + amount % denominator * numerator / denominator;
+}
+
+#[near(serializers = [json])]
+pub struct VestingView {
+    vesting_id: U64,
+    beneficiary_id: AccountId,
+    total_amount: U128,
+    claimed_amount: U128,
+    vested_amount: U128,
+    unvested_amount: U128,
+    claimable_amount: U128,
+    start: U64,
+    cliff: U64,
+    end: U64,
+    revocable: bool,
+    revoked: bool,
+}
+
+#[near]
+impl TokenContract {
+    /// Moves `amount` of the owner's tokens into a new vesting grant for `beneficiary_id`
+    /// and returns its id. `cliff_duration` and `duration` are counted from `start`.
+    #[payable]
+    pub fn create_vesting(
+        &mut self,
+        beneficiary_id: AccountId,
+        amount: U128,
+        start: U64,
+        cliff_duration: U64,
+        duration: U64,
+        revocable: bool,
+    ) -> U64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        require!(duration.0 > 0, "duration must be positive");
+        require!(cliff_duration.0 <= duration.0, "cliff_duration can't exceed duration");
+
+        let contract_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&contract_id) {
+            self.token.internal_register_account(&contract_id);
+        }
+        self.token.internal_transfer(&self.owner_id.clone(), &contract_id, amount.0, Some("vesting".to_string()));
+
+        let vesting_id = self.next_vesting_id;
+        self.next_vesting_id += 1;
+        self.vestings.insert(&vesting_id, &VestingSchedule {
+            beneficiary_id: beneficiary_id.clone(),
+            total_amount: amount.0,
+            claimed_amount: 0,
+            start: start.0,
+            cliff: start.0 + cliff_duration.0,
+            end: start.0 + duration.0,
+            revocable,
+            revoked: false,
+        });
+        let mut vesting_ids = self.vestings_per_beneficiary.get(&beneficiary_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::BeneficiaryVestings { account_hash: env::sha256_array(beneficiary_id.as_bytes()) })
+        });
+        vesting_ids.push(&vesting_id);
+        self.vestings_per_beneficiary.insert(&beneficiary_id, &vesting_ids);
+        log!("Created vesting {} of {} for @{}", vesting_id, amount.0, beneficiary_id);
+
+        return U64(vesting_id);
+    }
+
+    /// Sends everything that has vested and not been claimed yet to the beneficiary.
+    #[payable]
+    pub fn claim_vesting(&mut self, vesting_id: U64) -> U128 {
+        assert_one_yocto();
+        self.assert_not_paused();
+        let mut vesting = self.vestings.get(&vesting_id.0).expect("Vesting not found");
+        require!(env::predecessor_account_id() == vesting.beneficiary_id, "Only beneficiary can claim");
+
+        let amount = vesting.claimable_amount(env::block_timestamp());
+        require!(amount > 0, "Nothing to claim");
+        vesting.claimed_amount += amount;
+        self.vestings.insert(&vesting_id.0, &vesting);
+
+        self.token.internal_transfer(&env::current_account_id(), &vesting.beneficiary_id, amount, Some("vesting claim".to_string()));
+        return U128(amount);
+    }
+
+    /// Stops a revocable grant and returns its unvested part to the owner. What has
+    /// vested so far stays claimable.
+    #[payable]
+    pub fn revoke_vesting(&mut self, vesting_id: U64) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut vesting = self.vestings.get(&vesting_id.0).expect("Vesting not found");
+        require!(vesting.revocable, "Vesting is not revocable");
+        require!(!vesting.revoked, "Vesting already revoked");
+
+        let vested_amount = vesting.vested_amount(env::block_timestamp());
+        let unvested_amount = vesting.total_amount - vested_amount;
+        vesting.total_amount = vested_amount;
+        vesting.revoked = true;
+        self.vestings.insert(&vesting_id.0, &vesting);
+
+        if unvested_amount > 0 {
+            self.token.internal_transfer(&env::current_account_id(), &self.owner_id.clone(), unvested_amount, Some("vesting revoked".to_string()));
+        }
+        log!("Revoked vesting {}, returned {}", vesting_id.0, unvested_amount);
+        return U128(unvested_amount);
+    }
+
+    pub fn get_vesting(&self, vesting_id: U64) -> Option<VestingView> {
+        return self.vestings.get(&vesting_id.0).map(|vesting| vesting_view(vesting_id.0, vesting));
+    }
+
+    /// Grants of `beneficiary_id` in the order they were created, `limit` (50 by default)
+    /// at a time.
+    pub fn get_vestings(&self, beneficiary_id: AccountId, from_index: Option<U64>, limit: Option<U64>) -> Vec<VestingView> {
+        let vesting_ids = match self.vestings_per_beneficiary.get(&beneficiary_id) {
+            Some(vesting_ids) => vesting_ids,
+            None => return Vec::new(),
+        };
+        let from_index = from_index.map_or(0, |index| index.0);
+        let limit = limit.map_or(50, |limit| limit.0);
+        return (from_index..std::cmp::min(from_index.saturating_add(limit), vesting_ids.len()))
+            .map(|index| {
+                let vesting_id = vesting_ids.get(index).unwrap();
+                vesting_view(vesting_id, self.vestings.get(&vesting_id).unwrap())
+            })
+            .collect();
+    }
+}
+
+fn vesting_view(vesting_id: u64, vesting: VestingSchedule) -> VestingView {
+    let vested_amount = vesting.vested_amount(env::block_timestamp());
+    return VestingView {
+        vesting_id: U64(vesting_id),
+        beneficiary_id: vesting.beneficiary_id,
+        total_amount: U128(vesting.total_amount),
+        claimed_amount: U128(vesting.claimed_amount),
+        vested_amount: U128(vested_amount),
+        unvested_amount: U128(vesting.total_amount - vested_amount),
+        claimable_amount: U128(vested_amount - vesting.claimed_amount),
+        start: U64(vesting.start),
+        cliff: U64(vesting.cliff),
+        end: U64(vesting.end),
+        revocable: vesting.revocable,
+        revoked: vesting.revoked,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> VestingSchedule {
+        VestingSchedule {
+            beneficiary_id: "alice.near".parse().unwrap(),
+            total_amount: 1_000,
+            claimed_amount: 0,
+            start: 100,
+            cliff: 200,
+            end: 1_100,
+            revocable: true,
+            revoked: false,
+        }
+    }
+
+    #[test]
+    fn nothing_vests_before_cliff() {
+        let vesting = schedule();
+        assert_eq!(vesting.vested_amount(0), 0);
+        assert_eq!(vesting.vested_amount(199), 0);
+    }
+
+    #[test]
+    fn vests_linearly_from_start_after_cliff() {
+        let vesting = schedule();
+        assert_eq!(vesting.vested_amount(200), 100);
+        assert_eq!(vesting.vested_amount(600), 500);
+        assert_eq!(vesting.vested_amount(1_100), 1_000);
+        assert_eq!(vesting.vested_amount(5_000), 1_000);
+    }
+
+    #[test]
+    fn large_grant_over_a_year_does_not_overflow() {
+        const YEAR: u64 = 365 * 24 * 60 * 60 * 1_000_000_000;
+        let total_amount: Balance = 10_000 * 10u128.pow(24);
+        let vesting = VestingSchedule {
+            total_amount,
+            start: 0,
+            cliff: 0,
+            end: YEAR,
+            ..schedule()
+        };
+        assert_eq!(vesting.vested_amount(YEAR / 4), total_amount / 4);
+        assert_eq!(vesting.vested_amount(YEAR / 2), total_amount / 2);
+        assert_eq!(vesting.vested_amount(YEAR - 1), total_amount - total_amount.div_ceil(YEAR as u128));
+    }
+
+    #[test]
+    fn mul_div_rounds_down() {
+        assert_eq!(mul_div(10, 1, 3), 3);
+        assert_eq!(mul_div(u128::MAX, 7, 7), u128::MAX);
+        assert_eq!(mul_div(u128::MAX, 1, 2), u128::MAX / 2);
+    }
+
+    #[test]
+    fn revoked_grant_keeps_vested_amount() {
+        let mut vesting = schedule();
+        vesting.total_amount = vesting.vested_amount(600);
+        vesting.revoked = true;
+        vesting.claimed_amount = 200;
+        assert_eq!(vesting.vested_amount(600), 500);
+        assert_eq!(vesting.claimable_amount(5_000), 300);
+    }
+}
@@ -0,0 +1,172 @@
+use near_sdk::{json_types::{U128, U64}, NearToken};
+use near_crypto::{KeyType, SecretKey};
+use serde_json::json;
+
+use token::permit::PermitPayload;
+
+use crate::common::utils::*;
+pub mod common;
+
+#[tokio::test]
+async fn test_allowance() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner) = init(&worker, initial_balance).await?;
+
+    let users = create_users(&worker, vec!["alice", "spender"], vec![10, 10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    let spender = users.get(1).unwrap().clone();
+    register_user(&ft_contract, alice.id()).await?;
+    register_user(&ft_contract, spender.id()).await?;
+
+    // 1. owner가 spender에게 100 토큰을 승인하면 spender가 대신 전송할 수 있다.
+    let allowance = U128::from(NearToken::from_near(100).as_yoctonear());
+    let res = owner
+        .call(ft_contract.id(), "ft_approve")
+        .args_json(json!({"spender_id": spender.id(), "amount": allowance}))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let amount = U128::from(NearToken::from_near(60).as_yoctonear());
+    let res = spender
+        .call(ft_contract.id(), "ft_transfer_from")
+        .args_json(json!({"owner_id": owner.id(), "receiver_id": alice.id(), "amount": amount}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(ft_balance_of(&ft_contract, alice.id()).await?, amount.0);
+
+    let remaining = ft_contract
+        .call("ft_allowance")
+        .args_json(json!({"owner_id": owner.id(), "spender_id": spender.id()}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(remaining.0, allowance.0 - amount.0);
+
+    // 2. 남은 allowance보다 많이 보낼 수는 없다.
+    let res = spender
+        .call(ft_contract.id(), "ft_transfer_from")
+        .args_json(json!({"owner_id": owner.id(), "receiver_id": alice.id(), "amount": amount}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Transfer amount exceeds allowance"));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn test_permit() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner) = init(&worker, initial_balance).await?;
+
+    let users = create_users(&worker, vec!["alice", "spender", "relayer"], vec![10, 10, 10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    let spender = users.get(1).unwrap().clone();
+    let relayer = users.get(2).unwrap().clone();
+    register_user(&ft_contract, alice.id()).await?;
+
+    let amount = U128::from(NearToken::from_near(100).as_yoctonear());
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer")
+        .args_json((alice.id(), amount, Option::<String>::None))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 1. alice가 permit 서명용 키를 등록한다.
+    let permit_secret_key = SecretKey::from_random(KeyType::ED25519);
+    let public_key = permit_secret_key.public_key().to_string();
+    let res = alice
+        .call(ft_contract.id(), "add_permit_key")
+        .args_json(json!({"public_key": public_key}))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 2. alice가 서명한 permit은 relayer가 제출한다.
+    let deadline = worker.view_block().await?.timestamp() + 3_600_000_000_000;
+    let permit = PermitPayload {
+        contract_id: ft_contract.id().as_str().parse()?,
+        owner_id: alice.id().as_str().parse()?,
+        spender_id: spender.id().as_str().parse()?,
+        amount: amount.0,
+        nonce: 0,
+        deadline,
+    };
+    let args = |signature: String| json!({
+        "permit": {
+            "owner_id": alice.id(),
+            "spender_id": spender.id(),
+            "amount": amount,
+            "nonce": U64(0),
+            "deadline": U64(deadline),
+        },
+        "public_key": public_key,
+        "signature": signature,
+    });
+
+    // 등록된 키가 아닌 키로 서명하면 거절된다.
+    let other_secret_key = SecretKey::from_random(KeyType::ED25519);
+    let res = relayer
+        .call(ft_contract.id(), "permit")
+        .args_json(args(other_secret_key.sign(&permit.hash()).to_string()))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Invalid signature"));
+
+    let signature = permit_secret_key.sign(&permit.hash()).to_string();
+    let res = relayer
+        .call(ft_contract.id(), "permit")
+        .args_json(args(signature.clone()))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let allowance = ft_contract
+        .call("ft_allowance")
+        .args_json(json!({"owner_id": alice.id(), "spender_id": spender.id()}))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(allowance, amount);
+
+    // 3. 같은 permit은 다시 쓸 수 없다.
+    let res = relayer
+        .call(ft_contract.id(), "permit")
+        .args_json(args(signature))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Invalid nonce"));
+
+    // 4. spender는 받은 allowance로 alice의 토큰을 옮길 수 있다.
+    let res = spender
+        .call(ft_contract.id(), "ft_transfer_from")
+        .args_json(json!({"owner_id": alice.id(), "receiver_id": owner.id(), "amount": amount}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(ft_balance_of(&ft_contract, alice.id()).await?, 0);
+
+    return Ok(());
+}
@@ -0,0 +1,107 @@
+use near_sdk::{json_types::U128, NearToken};
+use serde_json::json;
+
+use crate::common::utils::*;
+pub mod common;
+
+#[tokio::test]
+async fn test_pause() -> anyhow::Result<()> {
+    let initial_balance = U128::from(NearToken::from_near(10000).as_yoctonear());
+    let worker = near_workspaces::sandbox().await?;
+    let (ft_contract, owner) = init(&worker, initial_balance).await?;
+
+    let users = create_users(&worker, vec!["alice", "pauser"], vec![10, 10]).await?;
+    let alice = users.get(0).unwrap().clone();
+    let pauser = users.get(1).unwrap().clone();
+    register_user(&ft_contract, alice.id()).await?;
+
+    let amount = U128::from(NearToken::from_near(100).as_yoctonear());
+    let res = owner
+        .call(ft_contract.id(), "ft_transfer")
+        .args_json((alice.id(), amount, Option::<String>::None))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // 1. pauser 역할이 없으면 owner라도 정지할 수 없다.
+    let res = owner
+        .call(ft_contract.id(), "pause")
+        .args_json(json!({}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Only pauser can pause"));
+
+    let res = owner
+        .call(ft_contract.id(), "grant_role")
+        .args_json(json!({"role": "Pauser", "account_id": pauser.id()}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = pauser
+        .call(ft_contract.id(), "pause")
+        .args_json(json!({}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(ft_contract.call("is_paused").view().await?.json::<bool>()?);
+
+    // 2. 정지 중에는 전송과 소각이 모두 막힌다.
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer")
+        .args_json((owner.id(), U128(1), Option::<String>::None))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Token is paused"));
+
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer_call")
+        .args_json((owner.id(), U128(1), Option::<String>::None, "".to_string()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Token is paused"));
+
+    let res = alice
+        .call(ft_contract.id(), "burn")
+        .args_json(json!({"amount": U128(1)}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", res.receipt_failures()).contains("Token is paused"));
+    assert_eq!(ft_balance_of(&ft_contract, alice.id()).await?, amount.0);
+
+    // 3. 정지를 풀면 다시 전송할 수 있다.
+    let res = pauser
+        .call(ft_contract.id(), "unpause")
+        .args_json(json!({}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(ft_contract.id(), "ft_transfer")
+        .args_json((owner.id(), U128(1), Option::<String>::None))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(ft_balance_of(&ft_contract, alice.id()).await?, amount.0 - 1);
+
+    return Ok(());
+}